@@ -3,6 +3,7 @@ use NodeId;
 ///
 /// Describes the possible behaviors of the `Tree::remove_node` method.
 ///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RemoveBehavior {
     ///
     /// All children will be dropped recursively.  In other words, the entire sub-tree of the `Node`
@@ -195,6 +196,7 @@ pub enum InsertBehavior<'a> {
 ///
 /// Describes the possible behaviors of the `Tree::swap_nodes` method.
 ///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SwapBehavior {
     ///
     /// Take the children of the `Node`s being swapped with them.  In other words, this swaps the
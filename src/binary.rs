@@ -0,0 +1,361 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use super::*;
+
+const MAGIC: &[u8; 4] = b"IDTR";
+const VERSION: u8 = 1;
+
+// Node counts read from a stream are only trusted this far when allocating up front.
+const MAX_PREALLOCATED_NODES: usize = 1 << 24;
+
+///
+/// Turns the data of a `Node` into bytes and back, for `Tree::write_binary` and
+/// `Tree::read_binary`.
+///
+/// The bytes written by `encode` are handed back to `decode` exactly as they were, so they don't
+/// need to record their own length.
+///
+/// ```
+/// use std::io;
+/// use id_tree::*;
+///
+/// struct I32Codec;
+///
+/// impl BinaryCodec<i32> for I32Codec {
+///     fn encode(&mut self, data: &i32, out: &mut Vec<u8>) -> io::Result<()> {
+///         out.extend_from_slice(&data.to_le_bytes());
+///         Ok(())
+///     }
+///
+///     fn decode(&mut self, bytes: &[u8]) -> io::Result<i32> {
+///         let mut buf = [0; 4];
+///         if bytes.len() != buf.len() {
+///             return Err(io::Error::new(io::ErrorKind::InvalidData, "expected 4 bytes"));
+///         }
+///         buf.copy_from_slice(bytes);
+///         Ok(i32::from_le_bytes(buf))
+///     }
+/// }
+/// ```
+///
+pub trait BinaryCodec<T> {
+    ///
+    /// Appends the encoding of `data` to `out`.
+    ///
+    fn encode(&mut self, data: &T, out: &mut Vec<u8>) -> io::Result<()>;
+
+    ///
+    /// Decodes data previously written by `encode`.
+    ///
+    fn decode(&mut self, bytes: &[u8]) -> io::Result<T>;
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Writes the `Tree` in a compact binary format, encoding each `Node`'s data with `codec`.
+    ///
+    /// After a short header and the number of `Node`s, every `Node` is written in pre-order as its
+    /// number of children and the length of its data (both as variable-length integers) followed
+    /// by the data itself.  `NodeId`s aren't stored, and neither are `Node`s that can't be reached
+    /// from the root.
+    ///
+    /// The format is written a little at a time, so wrapping `w` in an `io::BufWriter` is
+    /// recommended.
+    ///
+    /// ```
+    /// # use std::io;
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// # struct I32Codec;
+    /// # impl BinaryCodec<i32> for I32Codec {
+    /// #     fn encode(&mut self, data: &i32, out: &mut Vec<u8>) -> io::Result<()> {
+    /// #         out.extend_from_slice(&data.to_le_bytes());
+    /// #         Ok(())
+    /// #     }
+    /// #     fn decode(&mut self, bytes: &[u8]) -> io::Result<i32> {
+    /// #         let mut buf = [0; 4];
+    /// #         buf.copy_from_slice(bytes);
+    /// #         Ok(i32::from_le_bytes(buf))
+    /// #     }
+    /// # }
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// tree.write_binary(&mut bytes, &mut I32Codec).unwrap();
+    ///
+    /// let read = Tree::read_binary(&mut &bytes[..], &mut I32Codec).unwrap();
+    /// assert_eq!(read, tree);
+    /// ```
+    ///
+    pub fn write_binary<W, C>(&self, w: &mut W, codec: &mut C) -> io::Result<()>
+    where
+        W: Write,
+        C: BinaryCodec<T>,
+    {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+
+        let root_id = match self.root_node_id() {
+            Some(root_id) => root_id,
+            None => return write_varint(w, 0),
+        };
+        let count = self.traverse_pre_order_ids(root_id).unwrap().count();
+        write_varint(w, count as u64)?;
+
+        let mut buf = Vec::new();
+        let mut stack = vec![root_id];
+        while let Some(node_id) = stack.pop() {
+            let node = self.get_unsafe(node_id);
+
+            buf.clear();
+            codec.encode(node.data(), &mut buf)?;
+            write_varint(w, node.children().len() as u64)?;
+            write_varint(w, buf.len() as u64)?;
+            w.write_all(&buf)?;
+
+            stack.extend(node.children().iter().rev());
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Reads a `Tree` written by `Tree::write_binary`, decoding each `Node`'s data with `codec`.
+    ///
+    /// The `Tree` is built in a single pass with room for all of its `Node`s set aside up front.
+    /// It is compact: its `Node`s take up the first slots in pre-order, with none left free.
+    ///
+    /// Returns an `io::Error` of kind `InvalidData` if the input isn't in the expected format, and
+    /// passes on any error from `r` or `codec`.
+    ///
+    pub fn read_binary<R, C>(r: &mut R, codec: &mut C) -> io::Result<Tree<T>>
+    where
+        R: Read,
+        C: BinaryCodec<T>,
+    {
+        let mut header = [0; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not an id_tree binary stream"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported id_tree binary version"));
+        }
+
+        let count = read_varint(r)?;
+        let count = usize::try_from(count).map_err(|_| invalid_data("too many Nodes"))?;
+        let mut tree = TreeBuilder::new()
+            .with_node_capacity(count.min(MAX_PREALLOCATED_NODES))
+            .build();
+
+        // the Nodes still waiting for children, with how many they are waiting for
+        let mut open: Vec<(NodeId, usize)> = Vec::new();
+        let mut buf = Vec::new();
+        for index in 0..count {
+            let parent = match open.last_mut() {
+                Some(&mut (ref parent_id, ref mut waiting)) => {
+                    *waiting -= 1;
+                    Some(parent_id.clone())
+                }
+                None if index > 0 => return Err(invalid_data("more than one root")),
+                None => None,
+            };
+
+            let child_count = read_varint(r)?;
+            if child_count >= (count - index) as u64 {
+                return Err(invalid_data("more children than Nodes"));
+            }
+            let child_count = child_count as usize;
+
+            let len = read_varint(r)?;
+            buf.clear();
+            r.take(len).read_to_end(&mut buf)?;
+            if (buf.len() as u64) < len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let data = codec.decode(&buf)?;
+
+            let node_id = NodeId { index };
+            match parent {
+                Some(ref parent_id) => tree.get_mut_unsafe(parent_id).add_child(node_id.clone()),
+                None => tree.root = Some(node_id.clone()),
+            }
+            tree.nodes.push(Some(Node {
+                data,
+                parent,
+                children: Vec::with_capacity(child_count),
+            }));
+
+            if child_count > 0 {
+                open.push((node_id, child_count));
+            }
+            while open.last().is_some_and(|&(_, waiting)| waiting == 0) {
+                open.pop();
+            }
+        }
+
+        if !open.is_empty() {
+            return Err(invalid_data("fewer Nodes than children"));
+        }
+
+        Ok(tree)
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+///
+/// Writes `value` as an unsigned LEB128 integer.
+///
+fn write_varint<W: Write>(w: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    w.write_all(&buf[..len])
+}
+
+///
+/// Reads an unsigned LEB128 integer.
+///
+fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        let bits = u64::from(byte[0] & 0x7f);
+        if shift == 63 && bits > 1 {
+            break;
+        }
+        value |= bits << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("variable-length integer is too long"))
+}
+
+#[cfg(test)]
+mod binary_tests {
+    use std::io;
+
+    use super::super::*;
+    use super::{read_varint, write_varint};
+
+    struct StringCodec;
+
+    impl BinaryCodec<String> for StringCodec {
+        fn encode(&mut self, data: &String, out: &mut Vec<u8>) -> io::Result<()> {
+            out.extend_from_slice(data.as_bytes());
+            Ok(())
+        }
+
+        fn decode(&mut self, bytes: &[u8]) -> io::Result<String> {
+            String::from_utf8(bytes.to_vec())
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        }
+    }
+
+    fn write(tree: &Tree<String>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        tree.write_binary(&mut bytes, &mut StringCodec).unwrap();
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> io::Result<Tree<String>> {
+        Tree::read_binary(&mut &bytes[..], &mut StringCodec)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new("root".to_string()), AsRoot).unwrap();
+        let removed = tree
+            .insert(Node::new("gone".to_string()), UnderNode(&root_id))
+            .unwrap();
+        let a_id = tree
+            .insert(Node::new("a".to_string()), UnderNode(&root_id))
+            .unwrap();
+        tree.insert(Node::new(String::new()), UnderNode(&a_id))
+            .unwrap();
+        tree.insert(Node::new("b".to_string()), UnderNode(&root_id))
+            .unwrap();
+        tree.remove_node(removed, DropChildren).unwrap();
+
+        let bytes = write(&tree);
+        assert_eq!(
+            bytes,
+            b"IDTR\x01\x04\x02\x04root\x01\x01a\x00\x00\x00\x01b".to_vec()
+        );
+
+        let read_tree = read(&bytes).unwrap();
+        assert_eq!(read_tree, tree);
+        assert_eq!(read_tree.nodes.len(), 4);
+        assert!(read_tree.free_ids.is_empty());
+        assert!(read_tree.validate().is_ok());
+
+        let empty = write(&Tree::new());
+        assert_eq!(empty, b"IDTR\x01\x00".to_vec());
+        assert!(read(&empty).unwrap().root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        fn kind(bytes: &[u8]) -> io::ErrorKind {
+            read(bytes).unwrap_err().kind()
+        }
+
+        assert_eq!(kind(b"XXXX\x01\x00"), io::ErrorKind::InvalidData);
+        assert_eq!(kind(b"IDTR\x02\x00"), io::ErrorKind::InvalidData);
+        // truncated payload
+        assert_eq!(
+            kind(b"IDTR\x01\x01\x00\x04ro"),
+            io::ErrorKind::UnexpectedEof
+        );
+        // two roots
+        assert_eq!(
+            kind(b"IDTR\x01\x02\x00\x01a\x00\x01b"),
+            io::ErrorKind::InvalidData
+        );
+        // a child that never comes
+        assert_eq!(
+            kind(b"IDTR\x01\x02\x02\x01a\x00\x01b"),
+            io::ErrorKind::InvalidData
+        );
+        // data the codec rejects
+        assert_eq!(
+            kind(b"IDTR\x01\x01\x00\x01\xff"),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_varint() {
+        for &value in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value).unwrap();
+            assert_eq!(read_varint(&mut &bytes[..]).unwrap(), value);
+        }
+
+        let too_long = [0xff; 11];
+        assert!(read_varint(&mut &too_long[..]).is_err());
+    }
+}
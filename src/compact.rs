@@ -0,0 +1,267 @@
+use std::mem;
+
+use super::*;
+
+///
+/// Records where `Tree::compact` or `Tree::compact_pre_order` moved each `Node`, so that
+/// `NodeId`s stored outside of the `Tree` can be brought up to date.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeIdRemap {
+    new_ids: Vec<Option<NodeId>>,
+}
+
+impl NodeIdRemap {
+    ///
+    /// Returns the `NodeId` the `Node` formerly at `old_id` has now, or `None` if there was no
+    /// `Node` at `old_id` when the `Tree` was compacted.
+    ///
+    pub fn get(&self, old_id: &NodeId) -> Option<&NodeId> {
+        self.new_ids.get(old_id.index).and_then(Option::as_ref)
+    }
+
+    ///
+    /// Replaces `node_id` with the `NodeId` its `Node` has now.
+    ///
+    /// Returns `false`, leaving `node_id` untouched, if there was no `Node` at `node_id` when the
+    /// `Tree` was compacted.
+    ///
+    pub fn update(&self, node_id: &mut NodeId) -> bool {
+        match self.get(node_id) {
+            Some(new_id) => {
+                *node_id = new_id.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn map(&self, old_id: &NodeId) -> NodeId {
+        self.get(old_id)
+            .expect("NodeIdRemap::map: Every Node referenced in a Tree is in the Tree.")
+            .clone()
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Moves every `Node` into a contiguous block of storage (keeping their relative order),
+    /// forgets about the slots left behind by removed `Node`s and gives back the spare storage.
+    ///
+    /// This invalidates every `NodeId` previously handed out.  The returned `NodeIdRemap` can be
+    /// used to update any that were kept.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let removed_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let mut child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(removed_id.clone(), DropChildren).unwrap();
+    ///
+    /// let remap = tree.compact();
+    /// assert_eq!(tree.capacity(), 2);
+    /// assert!(remap.get(&removed_id).is_none());
+    ///
+    /// assert!(remap.update(&mut child_id));
+    /// assert_eq!(tree.get(&child_id).unwrap().data(), &2);
+    /// ```
+    ///
+    pub fn compact(&mut self) -> NodeIdRemap {
+        let order = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, slot)| slot.is_some())
+            .map(|(index, _)| index)
+            .collect();
+        self.compact_into(order)
+    }
+
+    ///
+    /// Like `Tree::compact`, but puts the `Node`s in pre-order, so that walking down the `Tree`
+    /// touches memory in sequence.
+    ///
+    /// The root's sub-tree comes first, followed by the sub-trees of any `Node`s that have been
+    /// orphaned.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let first_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&first_id)).unwrap();
+    ///
+    /// let remap = tree.compact_pre_order();
+    /// let second_id = remap.get(&second_id).unwrap();
+    /// assert_eq!(tree.get(second_id).unwrap().data(), &2);
+    ///
+    /// let data: Vec<i32> = tree
+    ///     .traverse_pre_order(tree.root_node_id().unwrap())
+    ///     .unwrap()
+    ///     .map(|node| *node.data())
+    ///     .collect();
+    /// assert_eq!(data, vec![0, 1, 3, 2]);
+    /// ```
+    ///
+    pub fn compact_pre_order(&mut self) -> NodeIdRemap {
+        let parentless = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|&(_, slot)| slot.as_ref().is_some_and(|node| node.parent.is_none()))
+            .map(|(index, _)| index);
+        let tops: Vec<usize> = self
+            .root
+            .as_ref()
+            .map(|root_id| root_id.index)
+            .into_iter()
+            .chain(parentless)
+            .collect();
+
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len() - self.free_ids.len());
+        for top in tops {
+            if visited[top] {
+                continue;
+            }
+            let mut stack = vec![top];
+            while let Some(index) = stack.pop() {
+                visited[index] = true;
+                order.push(index);
+                let node = self.get_unsafe(&NodeId { index });
+                stack.extend(node.children.iter().rev().map(|child_id| child_id.index));
+            }
+        }
+
+        self.compact_into(order)
+    }
+
+    ///
+    /// Rebuilds the `Tree`'s storage with the `Node`s at the given (old) indices, in order.
+    ///
+    fn compact_into(&mut self, order: Vec<usize>) -> NodeIdRemap {
+        let mut new_ids = vec![None; self.nodes.len()];
+        for (new_index, &old_index) in order.iter().enumerate() {
+            new_ids[old_index] = Some(NodeId { index: new_index });
+        }
+        let remap = NodeIdRemap { new_ids };
+
+        let mut old_nodes = mem::take(&mut self.nodes);
+        let mut nodes = Vec::with_capacity(order.len());
+        for old_index in order {
+            let mut node = old_nodes[old_index]
+                .take()
+                .expect("Tree::compact_into: Only Nodes in the Tree are moved.");
+            node.parent = node.parent.map(|parent_id| remap.map(&parent_id));
+            for child_id in node.children.iter_mut() {
+                *child_id = remap.map(child_id);
+            }
+            nodes.push(Some(node));
+        }
+
+        self.nodes = nodes;
+        self.root = self.root.as_ref().map(|root_id| remap.map(root_id));
+        self.free_ids = Vec::new();
+
+        self.notify(|| TreeEvent::Compacted);
+        remap
+    }
+}
+
+#[cfg(test)]
+mod compact_tests {
+    use super::super::*;
+
+    #[test]
+    fn test_compact() {
+        use RemoveBehavior::*;
+
+        // 9 leaves a free slot in front of 1
+        tree!(let mut tree = root_id @ 0 => [removed @ 9, node_1 @ 1 => [node_3 @ 3], node_2 @ 2]);
+        tree.remove_node(removed, DropChildren).unwrap();
+        let before = tree.clone();
+
+        let remap = tree.compact();
+        assert_eq!(tree, before);
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.nodes.len(), 4);
+        assert!(tree.free_ids.is_empty());
+
+        // relative order is kept
+        for &(ref old_id, data, index) in &[
+            (root_id, 0, 0),
+            (node_1, 1, 1),
+            (node_2, 2, 3),
+            (node_3, 3, 2),
+        ] {
+            let mut node_id = old_id.clone();
+            assert!(remap.update(&mut node_id));
+            assert_eq!(node_id.index, index);
+            assert_eq!(tree.get(&node_id).unwrap().data(), &data);
+        }
+    }
+
+    #[test]
+    fn test_compact_pre_order() {
+        use RemoveBehavior::*;
+
+        // 9 leaves a free slot in front of 1
+        tree!(let mut tree = 0 => [removed @ 9, node_1 @ 1 => [node_3 @ 3], 2]);
+        tree.remove_node(removed, DropChildren).unwrap();
+        tree.remove_node(node_1.clone(), OrphanChildren).unwrap();
+
+        let remap = tree.compact_pre_order();
+        assert!(tree.validate().is_ok());
+        assert!(remap.get(&node_1).is_none());
+
+        // the orphaned 3 comes after the root's sub-tree
+        let data: Vec<i32> = tree
+            .nodes
+            .iter()
+            .map(|slot| *slot.as_ref().unwrap().data())
+            .collect();
+        assert_eq!(data, vec![0, 2, 3]);
+        assert_eq!(remap.get(&node_3), Some(&NodeId { index: 2 }));
+    }
+
+    #[test]
+    fn test_shrink_and_reserve() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        // 9 leaves a free slot in front of 1
+        tree!(let mut tree = root_id @ 0 => [removed @ 9, 1 => [node_3 @ 3], node_2 @ 2]);
+        tree.remove_node(removed, DropChildren).unwrap();
+        tree.remove_node(node_2, DropChildren).unwrap();
+
+        tree.shrink_to_fit();
+        assert_eq!(tree.nodes.len(), 4);
+        assert_eq!(tree.capacity(), 4);
+        assert_eq!(tree.free_ids, vec![NodeId { index: 1 }]);
+        assert_eq!(tree.get(&node_3).unwrap().data(), &3);
+
+        tree.reserve(10);
+        assert!(tree.capacity() >= 14);
+        let capacity = tree.capacity();
+        for i in 0..10 {
+            tree.insert(Node::new(i), UnderNode(&root_id)).unwrap();
+        }
+        assert_eq!(tree.capacity(), capacity);
+
+        let mut empty: Tree<i32> = Tree::new();
+        empty.shrink_to_fit();
+        assert_eq!(
+            empty.compact(),
+            NodeIdRemap {
+                new_ids: Vec::new()
+            }
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use super::*;
+
+///
+/// A position in a `Tree` that can be moved between related `Node`s without looking each one up
+/// by `NodeId`.
+///
+/// Created by `Tree::cursor`.  The moving methods return `false`, leaving the `Cursor` where it
+/// was, if there is no `Node` to move to.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<i32> = Tree::new();
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+/// tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+///
+/// let mut cursor = tree.cursor(&root_id).unwrap();
+/// assert!(cursor.first_child());
+/// assert!(cursor.next_sibling());
+/// assert_eq!(cursor.data(), &2);
+/// assert!(!cursor.next_sibling());
+/// assert!(cursor.parent());
+/// assert_eq!(cursor.node_id(), &root_id);
+/// ```
+///
+#[derive(Debug)]
+pub struct Cursor<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    current: NodeId,
+}
+
+impl<'a, T> Clone for Cursor<'a, T> {
+    fn clone(&self) -> Self {
+        Cursor {
+            tree: self.tree,
+            current: self.current.clone(),
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    ///
+    /// Returns the `NodeId` of the `Node` the `Cursor` is at.
+    ///
+    pub fn node_id(&self) -> &NodeId {
+        &self.current
+    }
+
+    ///
+    /// Returns the `Node` the `Cursor` is at.
+    ///
+    pub fn node(&self) -> &'a Node<T> {
+        self.tree.get_unsafe(&self.current)
+    }
+
+    ///
+    /// Returns the data of the `Node` the `Cursor` is at.
+    ///
+    pub fn data(&self) -> &'a T {
+        self.node().data()
+    }
+
+    ///
+    /// Moves to the parent of the current `Node`.
+    ///
+    pub fn parent(&mut self) -> bool {
+        let next = parent_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the first child of the current `Node`.
+    ///
+    pub fn first_child(&mut self) -> bool {
+        let next = first_child_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the sibling after the current `Node`.
+    ///
+    pub fn next_sibling(&mut self) -> bool {
+        let next = next_sibling_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the sibling before the current `Node`.
+    ///
+    pub fn prev_sibling(&mut self) -> bool {
+        let next = prev_sibling_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+}
+
+///
+/// A position in a `Tree` that can be moved between related `Node`s, and that can change the
+/// `Tree` around it.
+///
+/// Created by `Tree::cursor_mut`.  The moving methods return `false`, leaving the `CursorMut`
+/// where it was, if there is no `Node` to move to.  Changes are made through the `Tree`'s own
+/// methods, so observers are notified of them as usual.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<i32> = Tree::new();
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+///
+/// let mut cursor = tree.cursor_mut(&root_id).unwrap();
+/// cursor.insert_child(Node::new(1));
+/// assert!(cursor.first_child());
+/// cursor.insert_after(Node::new(3)).unwrap();
+/// *cursor.data_mut() = 2;
+///
+/// let data: Vec<i32> = tree.children(&root_id).unwrap().map(|node| *node.data()).collect();
+/// assert_eq!(data, vec![2, 3]);
+/// ```
+///
+#[derive(Debug)]
+pub struct CursorMut<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    current: NodeId,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    ///
+    /// Returns the `NodeId` of the `Node` the `CursorMut` is at.
+    ///
+    pub fn node_id(&self) -> &NodeId {
+        &self.current
+    }
+
+    ///
+    /// Returns the `Node` the `CursorMut` is at.
+    ///
+    pub fn node(&self) -> &Node<T> {
+        self.tree.get_unsafe(&self.current)
+    }
+
+    ///
+    /// Returns the data of the `Node` the `CursorMut` is at.
+    ///
+    pub fn data(&self) -> &T {
+        self.node().data()
+    }
+
+    ///
+    /// Returns a mutable reference to the data of the `Node` the `CursorMut` is at.
+    ///
+    pub fn data_mut(&mut self) -> &mut T {
+        self.tree.get_mut_unsafe(&self.current).data_mut()
+    }
+
+    ///
+    /// Returns a read-only `Cursor` at the same position.
+    ///
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            tree: self.tree,
+            current: self.current.clone(),
+        }
+    }
+
+    ///
+    /// Moves to the parent of the current `Node`.
+    ///
+    pub fn parent(&mut self) -> bool {
+        let next = parent_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the first child of the current `Node`.
+    ///
+    pub fn first_child(&mut self) -> bool {
+        let next = first_child_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the sibling after the current `Node`.
+    ///
+    pub fn next_sibling(&mut self) -> bool {
+        let next = next_sibling_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Moves to the sibling before the current `Node`.
+    ///
+    pub fn prev_sibling(&mut self) -> bool {
+        let next = prev_sibling_of(self.tree, &self.current);
+        move_to(&mut self.current, next)
+    }
+
+    ///
+    /// Adds `node` as the last child of the current `Node`, without moving the `CursorMut`.
+    ///
+    /// Returns the `NodeId` of the new `Node`.
+    ///
+    pub fn insert_child(&mut self, node: Node<T>) -> NodeId {
+        self.tree
+            .insert(node, InsertBehavior::UnderNode(&self.current))
+            .expect("CursorMut::insert_child: The current Node is in the Tree.")
+    }
+
+    ///
+    /// Adds `node` as the sibling right after the current `Node`, without moving the `CursorMut`.
+    ///
+    /// Returns the `NodeId` of the new `Node`, or gives `node` back if the current `Node` has no
+    /// parent to add it under.
+    ///
+    pub fn insert_after(&mut self, node: Node<T>) -> Result<NodeId, Node<T>> {
+        let parent_id = match parent_of(self.tree, &self.current) {
+            Some(parent_id) => parent_id,
+            None => return Err(node),
+        };
+        let position = sibling_position(self.tree, &parent_id, &self.current);
+
+        let node_id = self
+            .tree
+            .insert(node, InsertBehavior::UnderNode(&parent_id))
+            .expect("CursorMut::insert_after: The parent of the current Node is in the Tree.");
+        self.tree
+            .make_nth_sibling(&node_id, position + 1)
+            .expect("CursorMut::insert_after: The new Node is in the Tree.");
+        Ok(node_id)
+    }
+
+    ///
+    /// Removes the current `Node` from the `Tree` according to `behavior` and moves to its
+    /// parent.
+    ///
+    /// Returns `None`, removing nothing, if the current `Node` has no parent to move to.  Use
+    /// `Tree::remove_node` to remove such a `Node`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut cursor = tree.cursor_mut(&child_id).unwrap();
+    /// assert_eq!(cursor.remove(DropChildren).unwrap().data(), &1);
+    /// assert_eq!(cursor.node_id(), &root_id);
+    /// assert!(cursor.remove(DropChildren).is_none());
+    /// ```
+    ///
+    pub fn remove(&mut self, behavior: RemoveBehavior) -> Option<Node<T>> {
+        let parent_id = parent_of(self.tree, &self.current)?;
+        let node_id = std::mem::replace(&mut self.current, parent_id);
+        let node = self
+            .tree
+            .remove_node(node_id, behavior)
+            .expect("CursorMut::remove: The current Node is in the Tree.");
+        Some(node)
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Returns a `Cursor` at the `Node` with the given `NodeId`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is not valid for this `Tree`.
+    ///
+    pub fn cursor(&self, node_id: &NodeId) -> Result<Cursor<'_, T>, NodeIdError> {
+        self.get(node_id)?;
+        Ok(Cursor {
+            tree: self,
+            current: node_id.clone(),
+        })
+    }
+
+    ///
+    /// Returns a `CursorMut` at the `Node` with the given `NodeId`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is not valid for this `Tree`.
+    ///
+    pub fn cursor_mut(&mut self, node_id: &NodeId) -> Result<CursorMut<'_, T>, NodeIdError> {
+        self.get(node_id)?;
+        Ok(CursorMut {
+            tree: self,
+            current: node_id.clone(),
+        })
+    }
+}
+
+fn move_to(current: &mut NodeId, next: Option<NodeId>) -> bool {
+    match next {
+        Some(next) => {
+            *current = next;
+            true
+        }
+        None => false,
+    }
+}
+
+fn parent_of<T>(tree: &Tree<T>, node_id: &NodeId) -> Option<NodeId> {
+    tree.get_unsafe(node_id).parent().cloned()
+}
+
+fn first_child_of<T>(tree: &Tree<T>, node_id: &NodeId) -> Option<NodeId> {
+    tree.get_unsafe(node_id).children().first().cloned()
+}
+
+fn next_sibling_of<T>(tree: &Tree<T>, node_id: &NodeId) -> Option<NodeId> {
+    let parent_id = parent_of(tree, node_id)?;
+    let position = sibling_position(tree, &parent_id, node_id);
+    tree.get_unsafe(&parent_id)
+        .children()
+        .get(position + 1)
+        .cloned()
+}
+
+fn prev_sibling_of<T>(tree: &Tree<T>, node_id: &NodeId) -> Option<NodeId> {
+    let parent_id = parent_of(tree, node_id)?;
+    let position = sibling_position(tree, &parent_id, node_id).checked_sub(1)?;
+    tree.get_unsafe(&parent_id)
+        .children()
+        .get(position)
+        .cloned()
+}
+
+fn sibling_position<T>(tree: &Tree<T>, parent_id: &NodeId, node_id: &NodeId) -> usize {
+    tree.get_unsafe(parent_id)
+        .children()
+        .iter()
+        .position(|child_id| child_id == node_id)
+        .expect("cursor::sibling_position: A Node is among its parent's children.")
+}
+
+#[cfg(test)]
+mod cursor_tests {
+    use super::super::*;
+
+    fn child_data(tree: &Tree<i32>, node_id: &NodeId) -> Vec<i32> {
+        tree.children(node_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect()
+    }
+
+    #[test]
+    fn test_cursor_moves() {
+        tree!(let tree = root_id @ 0 => [1 => [4], 2, 3]);
+        let mut cursor = tree.cursor(&root_id).unwrap();
+
+        assert!(!cursor.parent());
+        assert!(!cursor.next_sibling());
+        assert!(!cursor.prev_sibling());
+        assert_eq!(cursor.node_id(), &root_id);
+
+        assert!(cursor.first_child());
+        assert_eq!(cursor.data(), &1);
+        assert!(!cursor.prev_sibling());
+        assert!(cursor.first_child());
+        assert_eq!(cursor.data(), &4);
+        assert!(!cursor.first_child());
+        assert!(cursor.parent());
+
+        let mut seen = vec![*cursor.data()];
+        while cursor.next_sibling() {
+            seen.push(*cursor.data());
+        }
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert!(cursor.prev_sibling());
+        assert_eq!(cursor.node().data(), &2);
+    }
+
+    #[test]
+    fn test_cursor_clone() {
+        use InsertBehavior::*;
+
+        // the data doesn't need to be Clone for the Cursor to be
+        struct Opaque(i32);
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(Opaque(0)), AsRoot).unwrap();
+        tree.insert(Node::new(Opaque(1)), UnderNode(&root_id))
+            .unwrap();
+
+        let mut cursor = tree.cursor(&root_id).unwrap();
+        let start = cursor.clone();
+        assert!(cursor.first_child());
+        assert_eq!(cursor.data().0, 1);
+        assert_eq!(start.data().0, 0);
+    }
+
+    #[test]
+    fn test_cursor_invalid_id() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = 0 => [1, node_2 @ 2]);
+        tree.remove_node(node_2.clone(), DropChildren).unwrap();
+        assert!(tree.cursor(&node_2).is_err());
+        assert!(tree.cursor_mut(&node_2).is_err());
+    }
+
+    #[test]
+    fn test_cursor_mut_edits() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [1 => [4], node_2 @ 2, 3]);
+        {
+            let mut cursor = tree.cursor_mut(&node_2).unwrap();
+            cursor.insert_after(Node::new(5)).unwrap();
+            let child_id = cursor.insert_child(Node::new(6));
+            assert_eq!(cursor.node_id(), &node_2);
+            assert_eq!(cursor.as_cursor().data(), &2);
+            *cursor.data_mut() = 20;
+
+            assert!(cursor.next_sibling());
+            assert_eq!(cursor.data(), &5);
+            assert!(cursor.prev_sibling());
+            assert!(cursor.first_child());
+            assert_eq!(cursor.node_id(), &child_id);
+
+            assert!(cursor.parent());
+            assert_eq!(cursor.remove(LiftChildren).unwrap().data(), &20);
+            assert_eq!(cursor.node_id(), &root_id);
+
+            let root_node = cursor.insert_after(Node::new(7)).unwrap_err();
+            assert_eq!(root_node.data(), &7);
+            assert!(cursor.remove(DropChildren).is_none());
+        }
+
+        assert_eq!(child_data(&tree, &root_id), vec![1, 5, 3, 6]);
+        assert!(tree.validate().is_ok());
+    }
+}
@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+
+use super::*;
+
+///
+/// Identifies the parent an `EditOp` attaches a `Node` to.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchRef {
+    ///
+    /// A `Node` that already exists in the `Tree` the script is applied to.
+    ///
+    Existing(NodeId),
+
+    ///
+    /// The `Node` created by the `n`th `EditOp::Insert` of the same script (counting from zero).
+    ///
+    Inserted(usize),
+}
+
+///
+/// A single step of an edit script produced by `diff`.
+///
+/// All positions are the index the `Node` will have among its parent's children once the
+/// operation has been applied.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditOp<T> {
+    ///
+    /// Insert a new `Node` containing `data`.  A `parent` of `None` inserts the `Node` as the root
+    /// of the `Tree`.
+    ///
+    Insert {
+        parent: Option<PatchRef>,
+        position: usize,
+        data: T,
+    },
+
+    ///
+    /// Remove a `Node`.  Any children it still has are left orphaned; the script always contains a
+    /// later `EditOp::Move` for each of them.
+    ///
+    Delete { node: NodeId },
+
+    ///
+    /// Replace the data of a `Node`.
+    ///
+    Update { node: NodeId, data: T },
+
+    ///
+    /// Move a `Node` (along with its children) to a new parent and/or position.
+    ///
+    Move {
+        node: NodeId,
+        parent: PatchRef,
+        position: usize,
+    },
+}
+
+///
+/// Computes an edit script that turns `old` into `new`.
+///
+/// `Node`s of the two `Tree`s are first matched up: the roots are always matched, then identical
+/// sub-trees are matched top-down, `Node`s whose children were matched into the same parent are
+/// matched bottom-up, and finally the remaining children of matched `Node`s are paired by equal
+/// data and then by position.  Unmatched `Node`s in `old` become `Delete`s, unmatched `Node`s in
+/// `new` become `Insert`s, and matched `Node`s produce `Update`s and `Move`s as needed.
+///
+/// The script refers to `Node`s by the `NodeId`s they have in `old`, so it must be applied to
+/// `old` itself (or a clone of it) with `apply_patch`.  Only the `Node`s reachable from each root
+/// are considered.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut old: Tree<&str> = Tree::new();
+/// let root_id = old.insert(Node::new("config"), AsRoot).unwrap();
+/// let name_id = old.insert(Node::new("name"), UnderNode(&root_id)).unwrap();
+/// old.insert(Node::new("id_tree"), UnderNode(&name_id)).unwrap();
+///
+/// let mut new = old.clone();
+/// new.insert(Node::new("edition"), UnderNode(&root_id)).unwrap();
+///
+/// let script = diff(&old, &new);
+/// assert_eq!(script.len(), 1);
+///
+/// apply_patch(&mut old, script).unwrap();
+/// assert_eq!(old, new);
+/// ```
+///
+pub fn diff<T>(old: &Tree<T>, new: &Tree<T>) -> Vec<EditOp<T>>
+where
+    T: PartialEq + Clone,
+{
+    let matching = Matching::new(old, new);
+
+    let mut work = old.clone();
+    let mut script = Vec::new();
+    let mut inserted = Vec::new();
+
+    // Deleting first keeps the sibling lists free of doomed Nodes, so matched Nodes that are
+    // already in place don't need to be moved around them.
+    if let Some(old_root) = old.root_node_id() {
+        for id in old.traverse_post_order_ids(old_root).unwrap() {
+            if !matching.old_to_new.contains_key(&id) {
+                emit(
+                    &mut work,
+                    &mut script,
+                    &mut inserted,
+                    EditOp::Delete { node: id },
+                );
+            }
+        }
+    }
+
+    let new_root = match new.root_node_id() {
+        Some(new_root) => new_root,
+        None => return script,
+    };
+
+    // The PatchRef of each new Node, once it exists in `work`.
+    let mut refs: HashMap<NodeId, PatchRef> = HashMap::new();
+
+    match matching.new_to_old.get(new_root) {
+        Some(old_root) => {
+            refs.insert(new_root.clone(), PatchRef::Existing(old_root.clone()));
+        }
+        None => {
+            let op = EditOp::Insert {
+                parent: None,
+                position: 0,
+                data: new.get_unsafe(new_root).data().clone(),
+            };
+            refs.insert(new_root.clone(), PatchRef::Inserted(inserted.len()));
+            emit(&mut work, &mut script, &mut inserted, op);
+        }
+    }
+
+    for new_id in new.traverse_pre_order_ids(new_root).unwrap() {
+        let new_node = new.get_unsafe(&new_id);
+        let parent_ref = refs[&new_id].clone();
+        let parent_id = resolve(&parent_ref, &inserted).expect("diff: Parents are visited first.");
+
+        if let Some(old_id) = matching.new_to_old.get(&new_id) {
+            if work.get_unsafe(old_id).data() != new_node.data() {
+                let op = EditOp::Update {
+                    node: old_id.clone(),
+                    data: new_node.data().clone(),
+                };
+                emit(&mut work, &mut script, &mut inserted, op);
+            }
+        }
+
+        // Children that are already below this Node in a compatible order stay where they are.
+        // Every other child is moved (or inserted) directly after its preceding sibling.
+        let keep = {
+            let current: HashMap<&NodeId, usize> = work
+                .get_unsafe(&parent_id)
+                .children()
+                .iter()
+                .enumerate()
+                .map(|(position, id)| (id, position))
+                .collect();
+            let positions: Vec<Option<usize>> = new_node
+                .children()
+                .iter()
+                .map(|child_id| {
+                    matching
+                        .new_to_old
+                        .get(child_id)
+                        .and_then(|old_child_id| current.get(old_child_id).cloned())
+                })
+                .collect();
+            longest_increasing(&positions)
+        };
+
+        for (index, child_id) in new_node.children().iter().enumerate() {
+            let old_child_id = matching.new_to_old.get(child_id);
+
+            if keep[index] {
+                let old_child_id = old_child_id.unwrap().clone();
+                refs.insert(child_id.clone(), PatchRef::Existing(old_child_id));
+                continue;
+            }
+
+            let position = match index {
+                0 => 0,
+                _ => {
+                    let previous_id = resolve(&refs[&new_node.children()[index - 1]], &inserted)
+                        .expect("diff: The previous sibling is placed first.");
+                    work.get_unsafe(&parent_id)
+                        .children()
+                        .iter()
+                        .filter(|id| Some(*id) != old_child_id)
+                        .position(|id| id == &previous_id)
+                        .unwrap()
+                        + 1
+                }
+            };
+
+            let op = match old_child_id {
+                Some(old_child_id) => {
+                    refs.insert(child_id.clone(), PatchRef::Existing(old_child_id.clone()));
+                    EditOp::Move {
+                        node: old_child_id.clone(),
+                        parent: parent_ref.clone(),
+                        position,
+                    }
+                }
+                None => {
+                    refs.insert(child_id.clone(), PatchRef::Inserted(inserted.len()));
+                    EditOp::Insert {
+                        parent: Some(parent_ref.clone()),
+                        position,
+                        data: new.get_unsafe(child_id).data().clone(),
+                    }
+                }
+            };
+            emit(&mut work, &mut script, &mut inserted, op);
+        }
+    }
+
+    script
+}
+
+///
+/// Applies an edit script produced by `diff` to `tree`.
+///
+/// `tree` must be the `Tree` that was passed to `diff` as `old` (or a clone of it) in the state it
+/// was in at that time.
+///
+/// Returns a `Result` containing the `NodeId`s of the inserted `Node`s, in script order, or a
+/// `NodeIdError` if the script refers to a `Node` that no longer exists or to an `Insert` that
+/// doesn't come before the reference.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut old: Tree<i32> = Tree::new();
+/// let root_id = old.insert(Node::new(0), AsRoot).unwrap();
+/// old.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+///
+/// let script = vec![EditOp::Insert {
+///     parent: Some(PatchRef::Existing(root_id.clone())),
+///     position: 0,
+///     data: 2,
+/// }];
+///
+/// let inserted = apply_patch(&mut old, script).unwrap();
+///
+/// assert_eq!(old.get(&root_id).unwrap().children()[0], inserted[0]);
+/// ```
+///
+pub fn apply_patch<T>(
+    tree: &mut Tree<T>,
+    script: Vec<EditOp<T>>,
+) -> Result<Vec<NodeId>, NodeIdError> {
+    let mut inserted = Vec::new();
+    for op in script {
+        apply_op(tree, op, &mut inserted)?;
+    }
+    Ok(inserted)
+}
+
+fn emit<T>(
+    work: &mut Tree<T>,
+    script: &mut Vec<EditOp<T>>,
+    inserted: &mut Vec<NodeId>,
+    op: EditOp<T>,
+) where
+    T: Clone,
+{
+    script.push(op.clone());
+    apply_op(work, op, inserted).expect("diff: generated an edit script that doesn't apply");
+}
+
+fn apply_op<T>(
+    tree: &mut Tree<T>,
+    op: EditOp<T>,
+    inserted: &mut Vec<NodeId>,
+) -> Result<(), NodeIdError> {
+    match op {
+        EditOp::Insert {
+            parent,
+            position,
+            data,
+        } => {
+            let new_id = match parent {
+                Some(parent) => {
+                    let parent_id = resolve(&parent, inserted)?;
+                    let new_id =
+                        tree.insert(Node::new(data), InsertBehavior::UnderNode(&parent_id))?;
+                    tree.make_nth_sibling(&new_id, position)?;
+                    new_id
+                }
+                None => tree.insert(Node::new(data), InsertBehavior::AsRoot)?,
+            };
+            inserted.push(new_id);
+        }
+        EditOp::Delete { node } => {
+            tree.remove_node(node, RemoveBehavior::OrphanChildren)?;
+        }
+        EditOp::Update { node, data } => {
+            tree.replace_data(&node, data)?;
+        }
+        EditOp::Move {
+            node,
+            parent,
+            position,
+        } => {
+            let parent_id = resolve(&parent, inserted)?;
+            if tree.get(&node)?.parent() != Some(&parent_id) {
+                tree.move_node(&node, MoveBehavior::ToParent(&parent_id))?;
+            }
+            tree.make_nth_sibling(&node, position)?;
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Returns the `NodeId` a `PatchRef` stands for, or a `NodeIdError` if it is an
+/// `PatchRef::Inserted` for an `Insert` that hasn't happened (yet).
+///
+fn resolve(patch_ref: &PatchRef, inserted: &[NodeId]) -> Result<NodeId, NodeIdError> {
+    match *patch_ref {
+        PatchRef::Existing(ref id) => Ok(id.clone()),
+        PatchRef::Inserted(n) => inserted
+            .get(n)
+            .cloned()
+            .ok_or(NodeIdError::NodeIdNoLongerValid),
+    }
+}
+
+///
+/// A one-to-one mapping between the `Node`s of two `Tree`s.
+///
+struct Matching {
+    old_to_new: HashMap<NodeId, NodeId>,
+    new_to_old: HashMap<NodeId, NodeId>,
+}
+
+impl Matching {
+    fn new<T>(old: &Tree<T>, new: &Tree<T>) -> Matching
+    where
+        T: PartialEq,
+    {
+        let mut matching = Matching {
+            old_to_new: HashMap::new(),
+            new_to_old: HashMap::new(),
+        };
+
+        if let (Some(old_root), Some(new_root)) = (old.root_node_id(), new.root_node_id()) {
+            matching.match_identical(old, old_root, new, new_root);
+            matching.match_bottom_up(old, new, new_root);
+            matching.match_recovery(old, new, new_root);
+        }
+
+        matching
+    }
+
+    fn add(&mut self, old_id: &NodeId, new_id: &NodeId) {
+        self.old_to_new.insert(old_id.clone(), new_id.clone());
+        self.new_to_old.insert(new_id.clone(), old_id.clone());
+    }
+
+    fn add_subtree<T>(&mut self, old: &Tree<T>, old_id: &NodeId, new: &Tree<T>, new_id: &NodeId) {
+        let old_ids = old.traverse_pre_order_ids(old_id).unwrap();
+        let new_ids = new.traverse_pre_order_ids(new_id).unwrap();
+        for (old_id, new_id) in old_ids.zip(new_ids) {
+            self.add(&old_id, &new_id);
+        }
+    }
+
+    ///
+    /// Matches the roots, then walks `new` top-down matching each unmatched inner `Node` to an
+    /// identical sub-tree of `old`.  Leaves are left to the later passes so that common values
+    /// aren't matched across the whole `Tree`.
+    ///
+    fn match_identical<T>(
+        &mut self,
+        old: &Tree<T>,
+        old_root: &NodeId,
+        new: &Tree<T>,
+        new_root: &NodeId,
+    ) where
+        T: PartialEq,
+    {
+        if old.subtree_eq(old_root, new, new_root) {
+            self.add_subtree(old, old_root, new, new_root);
+            return;
+        }
+        self.add(old_root, new_root);
+
+        let mut candidates: HashMap<(usize, usize), Vec<NodeId>> = HashMap::new();
+        for (id, shape) in subtree_shapes(old, old_root) {
+            if shape.0 > 1 && &id != old_root {
+                candidates.entry(shape).or_default().push(id);
+            }
+        }
+        let new_shapes: HashMap<NodeId, (usize, usize)> = subtree_shapes(new, new_root).collect();
+
+        let mut stack = vec![new_root.clone()];
+        while let Some(new_id) = stack.pop() {
+            let new_node = new.get_unsafe(&new_id);
+
+            if !self.new_to_old.contains_key(&new_id) {
+                if let Some(ids) = candidates.get(&new_shapes[&new_id]) {
+                    let expected_parent = new_node
+                        .parent()
+                        .and_then(|parent_id| self.new_to_old.get(parent_id));
+
+                    // the new sub-tree is unmatched as a whole, since matches are only ever made
+                    // for whole sub-trees and this walk doesn't go below them; the old one has
+                    // to be checked, so that no Node is matched twice
+                    let is_match = |old_id: &&NodeId| {
+                        old.subtree_eq(old_id, new, &new_id)
+                            && old
+                                .traverse_pre_order_ids(old_id)
+                                .unwrap()
+                                .all(|id| !self.old_to_new.contains_key(&id))
+                    };
+                    // prefer a match that doesn't have to move
+                    let found = ids
+                        .iter()
+                        .filter(|old_id| old.get_unsafe(old_id).parent() == expected_parent)
+                        .find(&is_match)
+                        .or_else(|| ids.iter().find(&is_match))
+                        .cloned();
+
+                    if let Some(old_id) = found {
+                        self.add_subtree(old, &old_id, new, &new_id);
+                        continue;
+                    }
+                }
+            }
+
+            stack.extend(new_node.children().iter().rev().cloned());
+        }
+    }
+
+    ///
+    /// Walks `new` bottom-up, matching each unmatched `Node` to the unmatched `old` `Node` that most
+    /// of its children were matched into, provided the two share at least half of their children.
+    ///
+    fn match_bottom_up<T>(&mut self, old: &Tree<T>, new: &Tree<T>, new_root: &NodeId) {
+        for new_id in new.traverse_post_order_ids(new_root).unwrap() {
+            if self.new_to_old.contains_key(&new_id) {
+                continue;
+            }
+
+            let new_children = new.get_unsafe(&new_id).children();
+            let mut counts: Vec<(&NodeId, usize)> = Vec::new();
+            for child_id in new_children {
+                let old_parent = self
+                    .new_to_old
+                    .get(child_id)
+                    .and_then(|old_child| old.get_unsafe(old_child).parent())
+                    .filter(|old_parent| !self.old_to_new.contains_key(*old_parent));
+
+                if let Some(old_parent) = old_parent {
+                    match counts.iter_mut().find(|&&mut (id, _)| id == old_parent) {
+                        Some(entry) => entry.1 += 1,
+                        None => counts.push((old_parent, 1)),
+                    }
+                }
+            }
+
+            let best = counts
+                .into_iter()
+                .fold(None, |best: Option<(&NodeId, usize)>, entry| match best {
+                    Some(best) if best.1 >= entry.1 => Some(best),
+                    _ => Some(entry),
+                });
+
+            if let Some((old_id, common)) = best {
+                let old_children = old.get_unsafe(old_id).children().len();
+                if 4 * common >= new_children.len() + old_children {
+                    let old_id = old_id.clone();
+                    self.add(&old_id, &new_id);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Walks `new` top-down, pairing the unmatched children of each matched `Node` with the
+    /// unmatched children of its counterpart: first by equal data, then by position.
+    ///
+    fn match_recovery<T>(&mut self, old: &Tree<T>, new: &Tree<T>, new_root: &NodeId)
+    where
+        T: PartialEq,
+    {
+        for new_id in new.traverse_pre_order_ids(new_root).unwrap() {
+            let old_id = match self.new_to_old.get(&new_id) {
+                Some(old_id) => old_id.clone(),
+                None => continue,
+            };
+
+            let mut old_children: Vec<&NodeId> = old
+                .get_unsafe(&old_id)
+                .children()
+                .iter()
+                .filter(|id| !self.old_to_new.contains_key(*id))
+                .collect();
+            let new_children: Vec<&NodeId> = new
+                .get_unsafe(&new_id)
+                .children()
+                .iter()
+                .filter(|id| !self.new_to_old.contains_key(*id))
+                .collect();
+
+            let mut leftover = Vec::new();
+            for new_child in new_children {
+                let data = new.get_unsafe(new_child).data();
+                match old_children
+                    .iter()
+                    .position(|id| old.get_unsafe(id).data() == data)
+                {
+                    Some(position) => {
+                        let old_child = old_children.remove(position);
+                        self.add(old_child, new_child);
+                    }
+                    None => leftover.push(new_child),
+                }
+            }
+
+            for (old_child, new_child) in old_children.into_iter().zip(leftover) {
+                self.add(old_child, new_child);
+            }
+        }
+    }
+}
+
+///
+/// Flags the entries that make up a longest strictly increasing subsequence of the `Some` values
+/// in `positions`.
+///
+fn longest_increasing(positions: &[Option<usize>]) -> Vec<bool> {
+    // tails[k] is the index of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; positions.len()];
+
+    for (index, position) in positions.iter().enumerate() {
+        let position = match *position {
+            Some(position) => position,
+            None => continue,
+        };
+        let length = tails
+            .binary_search_by(|&tail| positions[tail].unwrap().cmp(&position))
+            .unwrap_or_else(|length| length);
+
+        if length > 0 {
+            previous[index] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+
+    let mut keep = vec![false; positions.len()];
+    let mut next = tails.last().cloned();
+    while let Some(index) = next {
+        keep[index] = true;
+        next = previous[index];
+    }
+    keep
+}
+
+///
+/// Returns the `(height, size)` of every sub-tree below (and including) `root`.
+///
+fn subtree_shapes<T>(
+    tree: &Tree<T>,
+    root: &NodeId,
+) -> ::std::vec::IntoIter<(NodeId, (usize, usize))> {
+    let mut shapes: HashMap<NodeId, (usize, usize)> = HashMap::new();
+    let mut ordered = Vec::new();
+
+    for id in tree.traverse_post_order_ids(root).unwrap() {
+        let shape = tree
+            .get_unsafe(&id)
+            .children()
+            .iter()
+            .map(|child_id| shapes[child_id])
+            .fold((1, 1), |(height, size), (child_height, child_size)| {
+                (height.max(child_height + 1), size + child_size)
+            });
+        shapes.insert(id.clone(), shape);
+        ordered.push((id, shape));
+    }
+
+    ordered.into_iter()
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::super::*;
+
+    fn assert_round_trip(old: &Tree<i32>, new: &Tree<i32>) -> Vec<EditOp<i32>> {
+        let script = diff(old, new);
+        let mut patched = old.clone();
+        apply_patch(&mut patched, script.clone()).unwrap();
+        assert_eq!(&patched, new);
+        script
+    }
+
+    #[test]
+    fn test_identical() {
+        let old = tree!(0 => [1 => [3, 4], 2]);
+        let new = tree!(0 => [1 => [3, 4], 2]);
+        assert!(assert_round_trip(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_update() {
+        tree!(let old = 0 => [1 => [node_3 @ 3, 4], 2]);
+        let mut new = old.clone();
+        new.get_mut(&node_3).unwrap().replace_data(30);
+
+        let script = assert_round_trip(&old, &new);
+        assert_eq!(
+            script,
+            vec![EditOp::Update {
+                node: node_3.clone(),
+                data: 30,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_insert_and_delete() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        tree!(let old = 0 => [node_1 @ 1 => [3, 4], node_2 @ 2]);
+        let mut new = old.clone();
+        new.remove_node(node_1.clone(), DropChildren).unwrap();
+        let node_5 = new.insert(Node::new(5), UnderNode(&node_2)).unwrap();
+        new.insert(Node::new(6), UnderNode(&node_5)).unwrap();
+
+        let script = assert_round_trip(&old, &new);
+        let deletes = script
+            .iter()
+            .filter(|op| matches!(op, EditOp::Delete { .. }))
+            .count();
+        assert_eq!(deletes, 3);
+        assert_eq!(script.len(), 5);
+    }
+
+    #[test]
+    fn test_move() {
+        use MoveBehavior::*;
+
+        tree!(let old = 0 => [node_1 @ 1 => [3, 4], node_2 @ 2]);
+        let mut new = old.clone();
+        new.move_node(&node_1, ToParent(&node_2)).unwrap();
+
+        let script = assert_round_trip(&old, &new);
+        assert_eq!(
+            script,
+            vec![EditOp::Move {
+                node: node_1.clone(),
+                parent: PatchRef::Existing(node_2.clone()),
+                position: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reorder() {
+        tree!(let old = 0 => [node_1 @ 1 => [3, node_4 @ 4], 2]);
+        let mut new = old.clone();
+        new.make_nth_sibling(&node_1, 1).unwrap();
+        new.make_nth_sibling(&node_4, 0).unwrap();
+
+        assert_eq!(assert_round_trip(&old, &new).len(), 2);
+    }
+
+    #[test]
+    fn test_nested_identical_subtrees() {
+        // 1 => [2] is matched first, and then mustn't be matched again as part of 5's sub-tree
+        let old = tree!(0 => [5 => [1 => [2]]]);
+        let new = tree!(0 => [1 => [2], 5 => [1 => [2]]]);
+
+        assert_round_trip(&old, &new);
+        assert_round_trip(&new, &old);
+    }
+
+    #[test]
+    fn test_unrelated_trees() {
+        use InsertBehavior::*;
+
+        let old = tree!(0 => [1 => [3, 4], 2]);
+
+        let mut new = Tree::new();
+        let root_id = new.insert(Node::new(10), AsRoot).unwrap();
+        let child_id = new.insert(Node::new(11), UnderNode(&root_id)).unwrap();
+        new.insert(Node::new(12), UnderNode(&child_id)).unwrap();
+        new.insert(Node::new(13), UnderNode(&root_id)).unwrap();
+
+        assert_round_trip(&old, &new);
+        assert_round_trip(&new, &old);
+    }
+
+    #[test]
+    fn test_empty_trees() {
+        let old = tree!(0 => [1 => [3, 4], 2]);
+        let empty = Tree::new();
+
+        assert_round_trip(&old, &empty);
+        assert_eq!(assert_round_trip(&empty, &old).len(), 5);
+        assert!(assert_round_trip(&empty, &empty).is_empty());
+    }
+
+    #[test]
+    fn test_bad_inserted_ref() {
+        tree!(let mut tree = 0 => [1 => [node_3 @ 3, 4], 2]);
+        let before = tree.clone();
+
+        let script = vec![EditOp::Move {
+            node: node_3.clone(),
+            parent: PatchRef::Inserted(0),
+            position: 0,
+        }];
+        assert_eq!(
+            apply_patch(&mut tree, script),
+            Err(NodeIdError::NodeIdNoLongerValid)
+        );
+
+        let script = vec![EditOp::Insert {
+            parent: Some(PatchRef::Inserted(5)),
+            position: 0,
+            data: 7,
+        }];
+        assert!(apply_patch(&mut tree, script).is_err());
+        assert_eq!(tree, before);
+    }
+}
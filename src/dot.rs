@@ -0,0 +1,340 @@
+use std::fmt::{self, Display, Write};
+
+use super::*;
+
+type NodeFn<'a, T, R> = Box<dyn Fn(&Node<T>) -> R + 'a>;
+
+///
+/// Settings for `Tree::write_dot`.
+///
+/// By default, `Node`s are labelled with nothing but their name in the graph, no extra attributes
+/// are written, nothing is clustered and only the root's sub-tree is included.
+///
+pub struct DotOptions<'a, T: 'a> {
+    name: String,
+    label: Option<NodeFn<'a, T, String>>,
+    attributes: Option<NodeFn<'a, T, Vec<(String, String)>>>,
+    cluster: Option<NodeFn<'a, T, bool>>,
+    include_orphans: bool,
+}
+
+impl<'a, T> DotOptions<'a, T> {
+    ///
+    /// Creates a new `DotOptions` with the default settings.
+    ///
+    pub fn new() -> DotOptions<'a, T> {
+        DotOptions {
+            name: "tree".to_string(),
+            label: None,
+            attributes: None,
+            cluster: None,
+            include_orphans: false,
+        }
+    }
+
+    ///
+    /// Sets the name of the graph.  Defaults to `tree`.
+    ///
+    pub fn with_name(mut self, name: &str) -> DotOptions<'a, T> {
+        self.name = name.to_string();
+        self
+    }
+
+    ///
+    /// Labels every `Node` with the `String` returned by `label`.
+    ///
+    pub fn with_label<F>(mut self, label: F) -> DotOptions<'a, T>
+    where
+        F: Fn(&Node<T>) -> String + 'a,
+    {
+        self.label = Some(Box::new(label));
+        self
+    }
+
+    ///
+    /// Labels every `Node` with the `Display` formatting of its data.
+    ///
+    pub fn with_display_labels(self) -> DotOptions<'a, T>
+    where
+        T: Display,
+    {
+        self.with_label(|node| node.data().to_string())
+    }
+
+    ///
+    /// Adds the `(name, value)` attribute pairs returned by `attributes` to every `Node`, such as
+    /// `("color", "red")` or `("shape", "box")`.
+    ///
+    pub fn with_attributes<F>(mut self, attributes: F) -> DotOptions<'a, T>
+    where
+        F: Fn(&Node<T>) -> Vec<(String, String)> + 'a,
+    {
+        self.attributes = Some(Box::new(attributes));
+        self
+    }
+
+    ///
+    /// Draws a box (a cluster) around the sub-tree of every `Node` for which `cluster` returns
+    /// `true`, labelled with the `Node`'s label.  Clusters may be nested.
+    ///
+    pub fn with_clusters<F>(mut self, cluster: F) -> DotOptions<'a, T>
+    where
+        F: Fn(&Node<T>) -> bool + 'a,
+    {
+        self.cluster = Some(Box::new(cluster));
+        self
+    }
+
+    ///
+    /// Sets whether `Node`s that aren't reachable from the root (such as the children left behind
+    /// by `RemoveBehavior::OrphanChildren`) are included, along with their sub-trees.
+    ///
+    pub fn with_orphans(mut self, include_orphans: bool) -> DotOptions<'a, T> {
+        self.include_orphans = include_orphans;
+        self
+    }
+}
+
+impl<'a, T> Default for DotOptions<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Step<'a> {
+    Enter(&'a NodeId),
+    CloseCluster,
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Writes the `Tree` as a Graphviz DOT graph, which can then be rendered with `dot`.
+    ///
+    /// Each `Node` is named after its `NodeId`'s position in the `Tree` (`n0`, `n1`, ...), and
+    /// children are drawn in order.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("root"), AsRoot).unwrap();
+    /// tree.insert(Node::new("a \"leaf\""), UnderNode(&root_id)).unwrap();
+    ///
+    /// let options = DotOptions::new().with_display_labels();
+    /// let mut s = String::new();
+    /// tree.write_dot(&mut s, &options).unwrap();
+    ///
+    /// assert_eq!(s, "\
+    /// digraph tree {
+    ///     n0 [label=\"root\"];
+    ///     n1 [label=\"a \\\"leaf\\\"\"];
+    ///     n0 -> n1;
+    /// }
+    /// ");
+    /// ```
+    ///
+    pub fn write_dot<W: Write>(&self, w: &mut W, options: &DotOptions<T>) -> fmt::Result {
+        writeln!(w, "digraph {} {{", quote_id(&options.name))?;
+
+        let orphans = if options.include_orphans {
+            self.orphan_ids()
+        } else {
+            Vec::new()
+        };
+        let tops: Vec<&NodeId> = self.root.iter().chain(orphans.iter()).collect();
+
+        let mut written = Vec::new();
+        let mut stack: Vec<Step> = tops.into_iter().rev().map(Step::Enter).collect();
+        let mut depth = 1;
+        while let Some(step) = stack.pop() {
+            let node_id = match step {
+                Step::Enter(node_id) => node_id,
+                Step::CloseCluster => {
+                    depth -= 1;
+                    writeln!(w, "{}}}", indent(depth))?;
+                    continue;
+                }
+            };
+            let node = self.get_unsafe(node_id);
+            let label = options.label.as_ref().map(|label| label(node));
+
+            if options
+                .cluster
+                .as_ref()
+                .is_some_and(|cluster| cluster(node))
+            {
+                writeln!(w, "{}subgraph cluster_{} {{", indent(depth), node_id.index)?;
+                depth += 1;
+                if let Some(ref label) = label {
+                    writeln!(w, "{}label={};", indent(depth), quote(label))?;
+                }
+                stack.push(Step::CloseCluster);
+            }
+
+            write!(w, "{}n{}", indent(depth), node_id.index)?;
+            let mut attributes = Vec::new();
+            if let Some(label) = label {
+                attributes.push(("label".to_string(), label));
+            }
+            if let Some(ref extra) = options.attributes {
+                attributes.extend(extra(node));
+            }
+            if !attributes.is_empty() {
+                let attributes: Vec<String> = attributes
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", quote_id(name), quote(value)))
+                    .collect();
+                write!(w, " [{}]", attributes.join(", "))?;
+            }
+            writeln!(w, ";")?;
+
+            written.push(node_id);
+            stack.extend(node.children().iter().rev().map(Step::Enter));
+        }
+
+        for node_id in written {
+            for child_id in self.get_unsafe(node_id).children() {
+                writeln!(w, "    n{} -> n{};", node_id.index, child_id.index)?;
+            }
+        }
+
+        writeln!(w, "}}")
+    }
+
+    ///
+    /// Returns the `NodeId`s of every `Node` other than the root that has no parent.
+    ///
+    fn orphan_ids(&self) -> Vec<NodeId> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|&(index, slot)| {
+                slot.as_ref().is_some_and(|node| node.parent().is_none())
+                    && self.root.as_ref().map(|root_id| root_id.index) != Some(index)
+            })
+            .map(|(index, _)| NodeId { index })
+            .collect()
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+///
+/// Writes `s` as a DOT string literal.
+///
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+///
+/// Writes `s` as a DOT identifier, quoting it unless it is a plain alphanumeric one.  Keywords
+/// (matched case-insensitively, as DOT does) are always quoted.
+///
+fn quote_id(s: &str) -> String {
+    const KEYWORDS: [&str; 6] = ["digraph", "edge", "graph", "node", "strict", "subgraph"];
+
+    let plain = !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(s));
+    if plain {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
+#[cfg(test)]
+mod dot_tests {
+    use super::super::*;
+
+    fn dot(tree: &Tree<i32>, options: &DotOptions<i32>) -> String {
+        let mut s = String::new();
+        tree.write_dot(&mut s, options).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree: Tree<i32> = Tree::new();
+        let options = DotOptions::new().with_name("my tree");
+        assert_eq!(dot(&tree, &options), "digraph \"my tree\" {\n}\n");
+
+        for name in &["graph", "Node", "EDGE", "digraph", "subGraph", "strict"] {
+            let options = DotOptions::new().with_name(name);
+            assert_eq!(
+                dot(&tree, &options),
+                format!("digraph \"{}\" {{\n}}\n", name)
+            );
+        }
+
+        let options = DotOptions::new().with_name("nodes");
+        assert_eq!(dot(&tree, &options), "digraph nodes {\n}\n");
+    }
+
+    #[test]
+    fn test_attributes_and_clusters() {
+        let tree = tree!(0 => [1 => [2], 3]);
+
+        let options = DotOptions::new()
+            .with_label(|node| format!("#{}\nnode", node.data()))
+            .with_attributes(|node| {
+                if node.children().is_empty() {
+                    vec![("shape".to_string(), "box".to_string())]
+                } else {
+                    Vec::new()
+                }
+            })
+            .with_clusters(|node| node.data() == &1);
+
+        assert_eq!(
+            dot(&tree, &options),
+            "digraph tree {\n\
+             \x20   n0 [label=\"#0\\nnode\"];\n\
+             \x20   subgraph cluster_1 {\n\
+             \x20       label=\"#1\\nnode\";\n\
+             \x20       n1 [label=\"#1\\nnode\"];\n\
+             \x20       n2 [label=\"#2\\nnode\", shape=\"box\"];\n\
+             \x20   }\n\
+             \x20   n3 [label=\"#3\\nnode\", shape=\"box\"];\n\
+             \x20   n0 -> n1;\n\
+             \x20   n0 -> n3;\n\
+             \x20   n1 -> n2;\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_orphans() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = 0 => [node_1 @ 1 => [2], 3]);
+        tree.remove_node(node_1, OrphanChildren).unwrap();
+
+        let without = dot(&tree, &DotOptions::new());
+        assert!(!without.contains("n2"));
+
+        let with = dot(&tree, &DotOptions::new().with_orphans(true));
+        assert_eq!(
+            with,
+            "digraph tree {\n    n0;\n    n3;\n    n2;\n    n0 -> n3;\n}\n"
+        );
+    }
+}
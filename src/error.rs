@@ -1,6 +1,8 @@
 use std::error::Error;
 use std::fmt;
 
+use NodeId;
+
 ///
 /// Enum for all of the possible `NodeId` errors that could occur.
 ///
@@ -32,3 +34,376 @@ impl Error for NodeIdError {
         self.to_string()
     }
 }
+
+///
+/// A way in which a `Tree`'s internal bookkeeping has become inconsistent, as reported by
+/// `Tree::validate`.
+///
+/// These can only come about through a bug in `id_tree` or through deserializing data that
+/// didn't come from a valid `Tree`.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InvariantViolation {
+    /// The root `NodeId` doesn't refer to a `Node` in the `Tree`.
+    RootNotInTree(NodeId),
+    /// The root `Node` has a parent.
+    RootHasParent(NodeId),
+    /// A `Node`'s parent doesn't refer to a `Node` in the `Tree`.
+    ParentNotInTree { node: NodeId, parent: NodeId },
+    /// A `Node` isn't amongst the children of its parent.
+    NotChildOfParent { node: NodeId, parent: NodeId },
+    /// One of a `Node`'s children doesn't refer to a `Node` in the `Tree`.
+    ChildNotInTree { node: NodeId, child: NodeId },
+    /// One of a `Node`'s children has a different parent.
+    ChildHasOtherParent { node: NodeId, child: NodeId },
+    /// A `Node` has the same child more than once.
+    DuplicateChild { node: NodeId, child: NodeId },
+    /// A `Node` is its own ancestor.  This is reported once for every cycle.
+    Cycle(NodeId),
+    /// A `Node` that isn't part of a cycle can't be reached from the root or any other parentless
+    /// `Node`, because it hangs below a cycle.
+    Unreachable(NodeId),
+    /// A free `NodeId` refers to a `Node` that is still in the `Tree`.
+    FreeIdInUse(NodeId),
+    /// A free `NodeId` is past the end of the `Tree`'s storage.
+    FreeIdOutOfBounds(NodeId),
+    /// A `NodeId` is free more than once.
+    DuplicateFreeId(NodeId),
+    /// An empty slot in the `Tree`'s storage isn't free, so it will never be reused.
+    SlotNotFree(NodeId),
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::InvariantViolation::*;
+
+        match *self {
+            RootNotInTree(ref root) => write!(f, "the root {:?} is not in the Tree", root),
+            RootHasParent(ref root) => write!(f, "the root {:?} has a parent", root),
+            ParentNotInTree {
+                ref node,
+                ref parent,
+            } => write!(
+                f,
+                "{:?} has a parent {:?} that is not in the Tree",
+                node, parent
+            ),
+            NotChildOfParent {
+                ref node,
+                ref parent,
+            } => write!(f, "{:?} is not a child of its parent {:?}", node, parent),
+            ChildNotInTree {
+                ref node,
+                ref child,
+            } => write!(
+                f,
+                "{:?} has a child {:?} that is not in the Tree",
+                node, child
+            ),
+            ChildHasOtherParent {
+                ref node,
+                ref child,
+            } => write!(
+                f,
+                "{:?} has a child {:?} whose parent is something else",
+                node, child
+            ),
+            DuplicateChild {
+                ref node,
+                ref child,
+            } => write!(f, "{:?} has the child {:?} more than once", node, child),
+            Cycle(ref node) => write!(f, "{:?} is its own ancestor", node),
+            Unreachable(ref node) => write!(f, "{:?} hangs below a cycle", node),
+            FreeIdInUse(ref node) => write!(f, "{:?} is free but in use", node),
+            FreeIdOutOfBounds(ref node) => write!(f, "{:?} is free but out of bounds", node),
+            DuplicateFreeId(ref node) => write!(f, "{:?} is free more than once", node),
+            SlotNotFree(ref node) => write!(f, "{:?} is neither in use nor free", node),
+        }
+    }
+}
+
+impl Error for InvariantViolation {
+    fn description(&self) -> &str {
+        "a Tree's internal bookkeeping is inconsistent"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `Tree::parse_indented`.  Each one records the
+/// (1-based) line it occurred on.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseIndentedError<E> {
+    /// The label parsing function failed on a line.
+    Label { line: usize, error: E },
+    /// A line is indented as though it were a second root.
+    MultipleRoots { line: usize },
+    /// A line's indentation doesn't match that of any `Node` it could belong under, or mixes tabs
+    /// and spaces differently from the lines above it.
+    BadIndentation { line: usize },
+}
+
+impl<E> ParseIndentedError<E> {
+    ///
+    /// Returns the (1-based) line the error occurred on.
+    ///
+    pub fn line(&self) -> usize {
+        match *self {
+            ParseIndentedError::Label { line, .. }
+            | ParseIndentedError::MultipleRoots { line }
+            | ParseIndentedError::BadIndentation { line } => line,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ParseIndentedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseIndentedError::Label { line, ref error } => {
+                write!(f, "line {}: invalid label: {}", line, error)
+            }
+            ParseIndentedError::MultipleRoots { line } => {
+                write!(f, "line {}: the tree already has a root", line)
+            }
+            ParseIndentedError::BadIndentation { line } => {
+                write!(f, "line {}: indentation doesn't match any line above", line)
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for ParseIndentedError<E> {
+    fn description(&self) -> &str {
+        "failed to parse an indented tree"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `Tree::from_newick`.  Each one records the byte
+/// offset in the input it occurred at.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NewickError {
+    /// A character that can't appear at this point.
+    UnexpectedChar { position: usize, found: char },
+    /// The input ended before the tree was complete (or without a closing `;`).
+    UnexpectedEnd { position: usize },
+    /// A branch length that isn't a number.
+    InvalidLength { position: usize },
+    /// A quoted label or a comment that is never closed.
+    Unterminated { position: usize },
+}
+
+impl NewickError {
+    ///
+    /// Returns the byte offset in the input that the error occurred at.
+    ///
+    pub fn position(&self) -> usize {
+        match *self {
+            NewickError::UnexpectedChar { position, .. }
+            | NewickError::UnexpectedEnd { position }
+            | NewickError::InvalidLength { position }
+            | NewickError::Unterminated { position } => position,
+        }
+    }
+}
+
+impl fmt::Display for NewickError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NewickError::UnexpectedChar { position, found } => {
+                write!(f, "unexpected {:?} at byte {}", found, position)
+            }
+            NewickError::UnexpectedEnd { position } => {
+                write!(f, "unexpected end of input at byte {}", position)
+            }
+            NewickError::InvalidLength { position } => {
+                write!(f, "invalid branch length at byte {}", position)
+            }
+            NewickError::Unterminated { position } => {
+                write!(
+                    f,
+                    "unterminated quote or comment starting at byte {}",
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl Error for NewickError {
+    fn description(&self) -> &str {
+        "failed to parse a Newick tree"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `Tree::from_sexpr`.  Each one records the byte
+/// offset in the input it occurred at.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SexprError<E> {
+    /// The atom parsing function failed on the atom starting here.
+    Atom { position: usize, error: E },
+    /// A character that can't appear at this point, such as a list at the head of a list or
+    /// anything after the end of the tree.
+    UnexpectedChar { position: usize, found: char },
+    /// The input ended in the middle of a list or a quoted atom.
+    UnexpectedEnd { position: usize },
+    /// A `()`, which has no head to be a `Node`.
+    EmptyList { position: usize },
+}
+
+impl<E> SexprError<E> {
+    ///
+    /// Returns the byte offset in the input that the error occurred at.
+    ///
+    pub fn position(&self) -> usize {
+        match *self {
+            SexprError::Atom { position, .. }
+            | SexprError::UnexpectedChar { position, .. }
+            | SexprError::UnexpectedEnd { position }
+            | SexprError::EmptyList { position } => position,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for SexprError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SexprError::Atom {
+                position,
+                ref error,
+            } => {
+                write!(f, "invalid atom at byte {}: {}", position, error)
+            }
+            SexprError::UnexpectedChar { position, found } => {
+                write!(f, "unexpected {:?} at byte {}", found, position)
+            }
+            SexprError::UnexpectedEnd { position } => {
+                write!(f, "unexpected end of input at byte {}", position)
+            }
+            SexprError::EmptyList { position } => write!(f, "empty list at byte {}", position),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for SexprError<E> {
+    fn description(&self) -> &str {
+        "failed to parse an S-expression tree"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `Tree::get_many_mut` and `Tree::get_pair_mut`.
+/// Each one records the position(s) in the given list of `NodeId`s it concerns.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GetManyMutError {
+    /// The `NodeId` at this position is no longer valid.
+    NodeIdNoLongerValid { position: usize },
+    /// The same `NodeId` was given at both of these positions.
+    Duplicate { first: usize, second: usize },
+}
+
+impl fmt::Display for GetManyMutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GetManyMutError::NodeIdNoLongerValid { position } => {
+                write!(f, "the NodeId at position {} is no longer valid", position)
+            }
+            GetManyMutError::Duplicate { first, second } => write!(
+                f,
+                "the NodeIds at positions {} and {} are the same",
+                first, second
+            ),
+        }
+    }
+}
+
+impl Error for GetManyMutError {
+    fn description(&self) -> &str {
+        "couldn't borrow several Nodes at once"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `LinkedTree::insert_before` and
+/// `LinkedTree::insert_after`.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InsertSiblingError {
+    /// The sibling's `NodeId` is no longer valid.
+    NodeIdNoLongerValid,
+    /// The sibling has no parent (it is the root, or has been orphaned), so the new `Node` would
+    /// have nowhere to go.
+    NoParent,
+}
+
+impl fmt::Display for InsertSiblingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InsertSiblingError::NodeIdNoLongerValid => {
+                write!(f, "the sibling's NodeId is no longer valid")
+            }
+            InsertSiblingError::NoParent => write!(f, "the sibling has no parent"),
+        }
+    }
+}
+
+impl Error for InsertSiblingError {
+    fn description(&self) -> &str {
+        "couldn't insert a Node beside its sibling"
+    }
+}
+
+///
+/// Enum for all of the errors that can occur in `Tree::from_parent_links`.  Each one records the
+/// key(s) of the record(s) it concerns.
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParentLinksError<K> {
+    /// More than one record has this key.
+    DuplicateKey(K),
+    /// Both of these records have no parent.
+    MultipleRoots { first: K, second: K },
+    /// The record with key `key` names a parent that no record has as its key.
+    MissingParent { key: K, parent: K },
+    /// The record with this key is its own ancestor.
+    Cycle(K),
+}
+
+impl<K: fmt::Debug> fmt::Display for ParentLinksError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParentLinksError::DuplicateKey(ref key) => {
+                write!(f, "more than one record has the key {:?}", key)
+            }
+            ParentLinksError::MultipleRoots {
+                ref first,
+                ref second,
+            } => write!(
+                f,
+                "the records {:?} and {:?} both have no parent",
+                first, second
+            ),
+            ParentLinksError::MissingParent {
+                ref key,
+                ref parent,
+            } => write!(
+                f,
+                "the record {:?} has the parent {:?}, which doesn't exist",
+                key, parent
+            ),
+            ParentLinksError::Cycle(ref key) => {
+                write!(f, "the record {:?} is its own ancestor", key)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for ParentLinksError<K> {
+    fn description(&self) -> &str {
+        "couldn't build a Tree from parent links"
+    }
+}
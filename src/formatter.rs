@@ -0,0 +1,286 @@
+use std::fmt::{self, Debug, Display};
+
+use super::*;
+
+///
+/// The characters `TreeFormatter` draws the branches of a `Tree` with.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyphs {
+    ///
+    /// Box-drawing characters: `├──`, `└──` and `│`.
+    ///
+    Unicode,
+    ///
+    /// Plain ASCII characters: `|--`, `` `-- `` and `|`.
+    ///
+    Ascii,
+}
+
+impl Glyphs {
+    /// (branch, last branch, line, horizontal)
+    fn parts(self) -> (char, char, char, char) {
+        match self {
+            Glyphs::Unicode => ('├', '└', '│', '─'),
+            Glyphs::Ascii => ('|', '`', '|', '-'),
+        }
+    }
+}
+
+type LabelFn<'a, T> = Box<dyn Fn(&Node<T>) -> String + 'a>;
+
+///
+/// Renders a `Tree` (or part of one) as indented text, one `Node` per line.
+///
+/// `TreeFormatter` implements `Display`, so it can be used with `format!`, `println!` and
+/// friends.  With the default settings, the output matches `Tree::write_formatted`.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<&str> = Tree::new();
+/// let root_id = tree.insert(Node::new("root"), AsRoot).unwrap();
+/// let child_id = tree.insert(Node::new("child"), UnderNode(&root_id)).unwrap();
+/// tree.insert(Node::new("grandchild"), UnderNode(&child_id)).unwrap();
+/// tree.insert(Node::new("two\nlines"), UnderNode(&root_id)).unwrap();
+///
+/// let formatter = TreeFormatter::display(&tree).with_glyphs(Glyphs::Ascii).with_indent(3);
+/// assert_eq!(formatter.to_string(), "\
+/// root
+/// |- child
+/// |  `- grandchild
+/// `- two
+///    lines
+/// ");
+///
+/// let formatter = TreeFormatter::display(&tree)
+///     .starting_at(&child_id)
+///     .unwrap()
+///     .with_max_depth(0);
+/// assert_eq!(formatter.to_string(), "child\n");
+/// ```
+///
+pub struct TreeFormatter<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    start: Option<&'a NodeId>,
+    label: LabelFn<'a, T>,
+    glyphs: Glyphs,
+    indent: usize,
+    max_depth: Option<usize>,
+}
+
+impl<'a, T> TreeFormatter<'a, T> {
+    ///
+    /// Creates a new `TreeFormatter` that labels each `Node` with the `String` returned by
+    /// `label`.  Labels may span several lines.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = TreeBuilder::new().with_root(Node::new(5)).build();
+    /// let formatter = TreeFormatter::new(&tree, |node| format!("<{}>", node.data()));
+    ///
+    /// assert_eq!(formatter.to_string(), "<5>\n");
+    /// ```
+    ///
+    pub fn new<F>(tree: &'a Tree<T>, label: F) -> TreeFormatter<'a, T>
+    where
+        F: Fn(&Node<T>) -> String + 'a,
+    {
+        TreeFormatter {
+            tree,
+            start: tree.root_node_id(),
+            label: Box::new(label),
+            glyphs: Glyphs::Unicode,
+            indent: 4,
+            max_depth: None,
+        }
+    }
+
+    ///
+    /// Creates a new `TreeFormatter` that labels each `Node` with the `Display` formatting of its
+    /// data.
+    ///
+    pub fn display(tree: &'a Tree<T>) -> TreeFormatter<'a, T>
+    where
+        T: Display,
+    {
+        TreeFormatter::new(tree, |node| node.data().to_string())
+    }
+
+    ///
+    /// Creates a new `TreeFormatter` that labels each `Node` with the `Debug` formatting of its
+    /// data.
+    ///
+    pub fn debug(tree: &'a Tree<T>) -> TreeFormatter<'a, T>
+    where
+        T: Debug,
+    {
+        TreeFormatter::new(tree, |node| format!("{:?}", node.data()))
+    }
+
+    ///
+    /// Renders the sub-tree of the given `Node` instead of the whole `Tree`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn starting_at(mut self, node_id: &'a NodeId) -> Result<TreeFormatter<'a, T>, NodeIdError> {
+        self.tree.get(node_id)?;
+        self.start = Some(node_id);
+        Ok(self)
+    }
+
+    ///
+    /// Sets the characters the branches are drawn with.  Defaults to `Glyphs::Unicode`.
+    ///
+    pub fn with_glyphs(mut self, glyphs: Glyphs) -> TreeFormatter<'a, T> {
+        self.glyphs = glyphs;
+        self
+    }
+
+    ///
+    /// Sets how many columns each level is indented by.  Defaults to 4, and can't be less than 2.
+    ///
+    pub fn with_indent(mut self, indent: usize) -> TreeFormatter<'a, T> {
+        self.indent = indent.max(2);
+        self
+    }
+
+    ///
+    /// Leaves out every `Node` more than `max_depth` levels below the starting `Node`.
+    ///
+    pub fn with_max_depth(mut self, max_depth: usize) -> TreeFormatter<'a, T> {
+        self.max_depth = Some(max_depth);
+        self
+    }
+}
+
+impl<'a, T> Display for TreeFormatter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let start = match self.start {
+            Some(start) => start,
+            None => return Ok(()),
+        };
+
+        let (branch, last_branch, line, horizontal) = self.glyphs.parts();
+        let dashes: String = (0..self.indent - 2).map(|_| horizontal).collect();
+        let branch = format!("{}{} ", branch, dashes);
+        let last_branch = format!("{}{} ", last_branch, dashes);
+        let line = format!("{:width$}", line, width = self.indent);
+        let blank = " ".repeat(self.indent);
+
+        // `prefix` is what goes before the connector of every Node at the next level down.
+        let mut stack = vec![(start, String::new(), None, 0)];
+        while let Some((node_id, prefix, is_last, depth)) = stack.pop() {
+            let node = self.tree.get_unsafe(node_id);
+            let label = (self.label)(node);
+
+            // continuation lines of the label line up with the first one, and so do the children
+            let (connector, child_prefix) = match is_last {
+                None => (String::new(), String::new()),
+                Some(true) => (
+                    format!("{}{}", prefix, last_branch),
+                    format!("{}{}", prefix, blank),
+                ),
+                Some(false) => (
+                    format!("{}{}", prefix, branch),
+                    format!("{}{}", prefix, line),
+                ),
+            };
+
+            let mut lines = label.lines();
+            writeln!(f, "{}{}", connector, lines.next().unwrap_or(""))?;
+            for text in lines {
+                if text.is_empty() {
+                    writeln!(f, "{}", child_prefix.trim_end())?;
+                } else {
+                    writeln!(f, "{}{}", child_prefix, text)?;
+                }
+            }
+
+            if self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+                continue;
+            }
+
+            let children = node.children();
+            for (i, child_id) in children.iter().enumerate().rev() {
+                stack.push((
+                    child_id,
+                    child_prefix.clone(),
+                    Some(i + 1 == children.len()),
+                    depth + 1,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tree_formatter_tests {
+    use super::super::*;
+
+    #[test]
+    fn test_glyphs() {
+        let tree = tree!(0 => [1 => [3], 2]);
+
+        let formatter = TreeFormatter::debug(&tree).with_glyphs(Glyphs::Ascii);
+        assert_eq!(formatter.to_string(), "0\n|-- 1\n|   `-- 3\n`-- 2\n");
+
+        let formatter = TreeFormatter::debug(&tree).with_indent(6);
+        assert_eq!(
+            formatter.to_string(),
+            "0\n├──── 1\n│     └──── 3\n└──── 2\n"
+        );
+
+        assert_eq!(TreeFormatter::debug(&Tree::<i32>::new()).to_string(), "");
+    }
+
+    #[test]
+    fn test_max_depth_and_start() {
+        tree!(let tree = 0 => [node_1 @ 1 => [3], 2]);
+
+        let formatter = TreeFormatter::display(&tree).with_max_depth(1);
+        assert_eq!(formatter.to_string(), "0\n├── 1\n└── 2\n");
+
+        let formatter = TreeFormatter::display(&tree).starting_at(&node_1).unwrap();
+        assert_eq!(formatter.to_string(), "1\n└── 3\n");
+    }
+
+    #[test]
+    fn test_multi_line_labels() {
+        let tree = tree!(0 => [1 => [3], 2]);
+
+        let formatter = TreeFormatter::new(&tree, |node| {
+            if node.data() % 2 == 0 {
+                format!("node {}\n\n(even)", node.data())
+            } else {
+                format!("node {}", node.data())
+            }
+        })
+        .with_indent(2);
+
+        assert_eq!(
+            formatter.to_string(),
+            "node 0\n\
+             \n\
+             (even)\n\
+             ├ node 1\n\
+             │ └ node 3\n\
+             └ node 2\n\
+             \n\
+             \x20 (even)\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_start() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = 0 => [1 => [node_3 @ 3], 2]);
+        tree.remove_node(node_3.clone(), DropChildren).unwrap();
+        assert!(TreeFormatter::display(&tree).starting_at(&node_3).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+use journal::Changeset;
+
+use super::*;
+
+///
+/// A `Tree` that keeps a history of its structural edits so that they can be undone and redone.
+///
+/// A `HistoryTree` offers the same mutating methods as `Tree` and dereferences to the `Tree`
+/// itself for everything else.  Every change made through it is recorded into the current group
+/// of changes until `checkpoint` is called, and `undo`/`redo` always work on whole groups.
+///
+/// Undoing a group restores the `Tree` exactly as it was, so `NodeId`s that were valid at that
+/// point are valid again and refer to the same `Node`s.  Redoing it likewise hands the re-inserted
+/// `Node`s the same `NodeId`s they had the first time around.
+///
+/// Because removed data has to be restored on undo, `remove_node` and `replace_data` don't hand
+/// the old data back to the caller.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: HistoryTree<i32> = HistoryTree::new(Tree::new());
+///
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// tree.checkpoint();
+///
+/// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+/// tree.replace_data(&root_id, 10).unwrap();
+///
+/// assert!(tree.undo());
+/// assert!(tree.get(&child_id).is_err());
+/// assert_eq!(tree.get(&root_id).unwrap().data(), &0);
+///
+/// assert!(tree.redo());
+/// assert_eq!(tree.get(&child_id).unwrap().data(), &1);
+/// assert_eq!(tree.get(&root_id).unwrap().data(), &10);
+/// ```
+///
+pub struct HistoryTree<T> {
+    tree: Tree<T>,
+    current: Option<Changeset<T>>,
+    undo_stack: Vec<Changeset<T>>,
+    redo_stack: Vec<Changeset<T>>,
+}
+
+impl<T> HistoryTree<T> {
+    ///
+    /// Creates a new `HistoryTree` with an empty history, starting from the `Tree` provided.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let _tree: HistoryTree<i32> = HistoryTree::new(Tree::new());
+    /// ```
+    ///
+    pub fn new(tree: Tree<T>) -> HistoryTree<T> {
+        HistoryTree {
+            tree,
+            current: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    ///
+    /// Discards the history and returns the `Tree` in its current state.
+    ///
+    pub fn into_inner(self) -> Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Ends the current group of changes.  The next change starts a new group.
+    ///
+    /// Does nothing if no changes have been made since the last checkpoint.
+    ///
+    pub fn checkpoint(&mut self) {
+        if let Some(changes) = self.current.take() {
+            if !changes.is_empty() {
+                self.undo_stack.push(changes);
+            }
+        }
+    }
+
+    ///
+    /// Returns `true` if there is a group of changes that `undo` would revert.
+    ///
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty() || self.current.as_ref().is_some_and(|c| !c.is_empty())
+    }
+
+    ///
+    /// Returns `true` if there is a group of changes that `redo` would re-apply.
+    ///
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    ///
+    /// Reverts the most recent group of changes, ending the current group first.
+    ///
+    /// Returns `false` if there was nothing to undo.
+    ///
+    pub fn undo(&mut self) -> bool {
+        self.checkpoint();
+
+        match self.undo_stack.pop() {
+            Some(mut changes) => {
+                changes.revert(&mut self.tree);
+                self.redo_stack.push(changes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Re-applies the most recently undone group of changes.
+    ///
+    /// Making any new change discards the groups that could be redone.  Returns `false` if there
+    /// was nothing to redo.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: HistoryTree<i32> = HistoryTree::new(Tree::new());
+    ///
+    /// tree.insert(Node::new(0), AsRoot).unwrap();
+    /// tree.undo();
+    /// assert!(tree.can_redo());
+    ///
+    /// tree.insert(Node::new(1), AsRoot).unwrap();
+    /// assert!(!tree.redo());
+    /// ```
+    ///
+    pub fn redo(&mut self) -> bool {
+        self.checkpoint();
+
+        match self.redo_stack.pop() {
+            Some(mut changes) => {
+                changes.revert(&mut self.tree);
+                self.undo_stack.push(changes);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Forgets every recorded change without touching the `Tree`.
+    ///
+    pub fn clear_history(&mut self) {
+        self.current = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    fn record<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Changeset<T>, &mut Tree<T>) -> R,
+    {
+        let tree = &mut self.tree;
+        let changes = self.current.get_or_insert_with(|| Changeset::new(tree));
+
+        let result = f(changes, tree);
+        if !changes.is_empty() {
+            self.redo_stack.clear();
+        }
+        result
+    }
+
+    ///
+    /// Inserts a new `Node` into the `Tree`, as with `Tree::insert`.
+    ///
+    pub fn insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        self.record(|changes, tree| changes.insert(tree, node, behavior))
+    }
+
+    ///
+    /// Removes a `Node` from the `Tree`, as with `Tree::remove_node`.
+    ///
+    /// The removed `Node` isn't returned, since it is kept around in case it has to be put back.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: HistoryTree<i32> = HistoryTree::new(Tree::new());
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.checkpoint();
+    ///
+    /// tree.remove_node(root_id.clone(), DropChildren).unwrap();
+    /// assert!(tree.root_node_id().is_none());
+    ///
+    /// tree.undo();
+    /// assert_eq!(tree.root_node_id(), Some(&root_id));
+    /// assert_eq!(tree.get(&child_id).unwrap().parent(), Some(&root_id));
+    /// ```
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.record(|changes, tree| changes.remove_node(tree, node_id, behavior))
+    }
+
+    ///
+    /// Moves a `Node` to a new location in the `Tree`, as with `Tree::move_node`.
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.record(|changes, tree| changes.move_node(tree, node_id, behavior))
+    }
+
+    ///
+    /// Swaps two `Node`s in the `Tree`, as with `Tree::swap_nodes`.
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.record(|changes, tree| changes.swap_nodes(tree, first_id, second_id, behavior))
+    }
+
+    ///
+    /// Sorts the children of a `Node`, as with `Tree::sort_children_by`.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.record(|changes, tree| changes.sort_children_by(tree, node_id, compare))
+    }
+
+    ///
+    /// Sorts the children of a `Node` by their data, as with `Tree::sort_children_by_data`.
+    ///
+    pub fn sort_children_by_data(&mut self, node_id: &NodeId) -> Result<(), NodeIdError>
+    where
+        T: Ord,
+    {
+        self.record(|changes, tree| changes.sort_children_by_data(tree, node_id))
+    }
+
+    ///
+    /// Sorts the children of a `Node` by a key, as with `Tree::sort_children_by_key`.
+    ///
+    pub fn sort_children_by_key<B, F>(&mut self, node_id: &NodeId, f: F) -> Result<(), NodeIdError>
+    where
+        B: Ord,
+        F: FnMut(&Node<T>) -> B,
+    {
+        self.record(|changes, tree| changes.sort_children_by_key(tree, node_id, f))
+    }
+
+    ///
+    /// Moves a `Node` to a position amongst its siblings, as with `Tree::make_nth_sibling`.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        self.record(|changes, tree| changes.make_nth_sibling(tree, node_id, pos))
+    }
+
+    ///
+    /// Makes a `Node` the first of its siblings, as with `Tree::make_first_sibling`.
+    ///
+    pub fn make_first_sibling(&mut self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        self.record(|changes, tree| changes.make_first_sibling(tree, node_id))
+    }
+
+    ///
+    /// Makes a `Node` the last of its siblings, as with `Tree::make_last_sibling`.
+    ///
+    pub fn make_last_sibling(&mut self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        self.record(|changes, tree| changes.make_last_sibling(tree, node_id))
+    }
+
+    ///
+    /// Replaces the data of a `Node`, as with `Node::replace_data`.
+    ///
+    /// The old data isn't returned, since it is kept around in case it has to be put back.
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<(), NodeIdError> {
+        self.record(|changes, tree| changes.replace_data(tree, node_id, data))
+    }
+}
+
+impl<T> Deref for HistoryTree<T> {
+    type Target = Tree<T>;
+
+    fn deref(&self) -> &Tree<T> {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod history_tree_tests {
+    use super::super::*;
+
+    fn snapshot(tree: &Tree<i32>) -> String {
+        format!("{:?}", tree)
+    }
+
+    #[test]
+    fn test_undo_redo_groups() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+        use SwapBehavior::*;
+
+        let mut tree = HistoryTree::new(Tree::new());
+        assert!(!tree.can_undo());
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        let node_3 = tree.insert(Node::new(3), UnderNode(&node_1)).unwrap();
+        tree.checkpoint();
+        let first = snapshot(&tree);
+
+        tree.move_node(&node_3, ToParent(&node_2)).unwrap();
+        tree.swap_nodes(&node_1, &node_2, TakeChildren).unwrap();
+        tree.checkpoint();
+        let second = snapshot(&tree);
+
+        tree.remove_node(node_2.clone(), DropChildren).unwrap();
+        tree.sort_children_by_data(&root_id).unwrap();
+        tree.make_first_sibling(&node_1).unwrap();
+        tree.replace_data(&root_id, 10).unwrap();
+        let third = snapshot(&tree);
+
+        assert!(tree.undo());
+        assert_eq!(snapshot(&tree), second);
+        assert!(tree.undo());
+        assert_eq!(snapshot(&tree), first);
+        assert!(tree.undo());
+        assert!(tree.root_node_id().is_none());
+        assert!(!tree.undo());
+
+        assert!(tree.redo());
+        assert_eq!(snapshot(&tree), first);
+        assert!(tree.redo());
+        assert_eq!(snapshot(&tree), second);
+        assert!(tree.redo());
+        assert_eq!(snapshot(&tree), third);
+        assert!(!tree.redo());
+    }
+
+    #[test]
+    fn test_new_changes_discard_redo() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use SwapBehavior::*;
+
+        let mut tree = HistoryTree::new(Tree::new());
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        tree.checkpoint();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        assert!(tree.undo());
+        assert!(tree.can_redo());
+
+        // a successful change discards the redo history
+        let other_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        assert!(!tree.can_redo());
+        assert!(tree.undo());
+        assert!(tree.get(&other_id).is_err());
+
+        // a failed change records nothing, so it doesn't discard the redo history
+        assert!(tree.insert(Node::new(3), UnderNode(&other_id)).is_err());
+        assert!(tree.can_redo());
+        assert!(tree.move_node(&root_id, ToParent(&other_id)).is_err());
+        assert!(tree.can_redo());
+        assert!(tree.swap_nodes(&root_id, &other_id, TakeChildren).is_err());
+        assert!(tree.can_redo());
+
+        tree.clear_history();
+        assert!(!tree.can_undo());
+        assert!(!tree.can_redo());
+        assert_eq!(tree.into_inner().get(&root_id).unwrap().data(), &0);
+    }
+}
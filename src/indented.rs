@@ -0,0 +1,173 @@
+use super::*;
+
+impl<T> Tree<T> {
+    ///
+    /// Builds a `Tree` from an indented outline, with one `Node` per line.
+    ///
+    /// Each line becomes a child of the closest line above it that is indented less.  Both plain
+    /// indentation (spaces or tabs, used consistently) and the box-drawing format written by
+    /// `write_formatted` and `TreeFormatter` are understood, so `write_formatted`'s output can be
+    /// read back in.  Blank lines are skipped.
+    ///
+    /// Each label, with the indentation and any trailing whitespace removed, is turned into the
+    /// `Node`'s data by `parse_label`.
+    ///
+    /// Returns a `ParseIndentedError`, carrying the line number, if a label can't be parsed, if a
+    /// line's indentation doesn't line up with the lines above it, or if there is more than one
+    /// root.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let outline = "
+    /// fruit
+    ///     apple
+    ///         granny smith
+    ///     pear
+    /// ";
+    /// let parse = |label: &str| Ok::<_, ()>(label.to_string());
+    /// let tree = Tree::parse_indented(outline, parse).unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_formatted(&mut s).unwrap();
+    /// assert_eq!(s, "\
+    /// \"fruit\"
+    /// ├── \"apple\"
+    /// │   └── \"granny smith\"
+    /// └── \"pear\"
+    /// ");
+    ///
+    /// let parsed = Tree::parse_indented(&s, |label: &str| Ok::<_, ()>(label.to_string())).unwrap();
+    /// # let root_id = parsed.root_node_id().unwrap();
+    /// # assert_eq!(parsed.get(root_id).unwrap().data(), "\"fruit\"");
+    /// # assert_eq!(parsed.height(), 3);
+    ///
+    /// let error = Tree::parse_indented("a\n    b\n  c", parse).unwrap_err();
+    /// assert_eq!(error, ParseIndentedError::BadIndentation { line: 3 });
+    /// ```
+    ///
+    pub fn parse_indented<F, E>(
+        text: &str,
+        mut parse_label: F,
+    ) -> Result<Tree<T>, ParseIndentedError<E>>
+    where
+        F: FnMut(&str) -> Result<T, E>,
+    {
+        let mut tree = Tree::new();
+        // the indentation of every line the next one could be a child of, and its Node
+        let mut open: Vec<(String, NodeId)> = Vec::new();
+
+        for (i, raw_line) in text.lines().enumerate() {
+            let line = i + 1;
+            let label_start = raw_line
+                .find(|c| !is_indentation(c))
+                .unwrap_or(raw_line.len());
+            let label = raw_line[label_start..].trim_end();
+            if label.is_empty() {
+                continue;
+            }
+
+            // box-drawing characters only stand in for indentation
+            let indent: String = raw_line[..label_start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            // A line indented further than the last one is its child.  Otherwise it has to line up
+            // exactly with a line above it, whose sibling it becomes.
+            let mut dedented = false;
+            while let Some((open_indent, _)) = open.last() {
+                if indent == *open_indent {
+                    open.pop();
+                    break;
+                }
+                if indent.starts_with(open_indent.as_str()) && !dedented {
+                    break;
+                }
+                if !open_indent.starts_with(indent.as_str()) {
+                    return Err(ParseIndentedError::BadIndentation { line });
+                }
+                open.pop();
+                dedented = true;
+            }
+
+            let data =
+                parse_label(label).map_err(|error| ParseIndentedError::Label { line, error })?;
+            let node_id = match open.last() {
+                Some((_, parent_id)) => tree
+                    .insert(Node::new(data), InsertBehavior::UnderNode(parent_id))
+                    .expect("Tree::parse_indented: The parent was just inserted."),
+                None if tree.root.is_some() => {
+                    return Err(ParseIndentedError::MultipleRoots { line })
+                }
+                None => tree
+                    .insert(Node::new(data), InsertBehavior::AsRoot)
+                    .expect("Tree::parse_indented: Inserting a root can't fail."),
+            };
+            open.push((indent, node_id));
+        }
+
+        Ok(tree)
+    }
+}
+
+fn is_indentation(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '│' | '├' | '└' | '─')
+}
+
+#[cfg(test)]
+mod parse_indented_tests {
+    use super::super::*;
+
+    fn parse(text: &str) -> Result<Tree<String>, ParseIndentedError<()>> {
+        Tree::parse_indented(text, |label| Ok(label.to_string()))
+    }
+
+    fn outline(tree: &Tree<String>) -> String {
+        TreeFormatter::display(tree).with_indent(2).to_string()
+    }
+
+    #[test]
+    fn test_plain_indentation() {
+        let error = parse("a\n\tb\n\t\tc\n\n\td  \r\ne").unwrap_err();
+        assert_eq!(error, ParseIndentedError::MultipleRoots { line: 6 });
+
+        let tree = parse("a\n\tb\n\t\tc\n\n\td  \r\n").unwrap();
+        assert_eq!(outline(&tree), "a\n├ b\n│ └ c\n└ d\n");
+
+        let error = parse("  a\n      b\n    c\n        d\n      e\n").unwrap_err();
+        assert_eq!(error, ParseIndentedError::BadIndentation { line: 3 });
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tree = parse("0\n  1\n    2\n      3\n    4\n  5\n    6\n").unwrap();
+
+        let mut s = String::new();
+        tree.write_formatted(&mut s).unwrap();
+        let parsed =
+            Tree::parse_indented(&s, |label| label.trim_matches('"').parse::<String>()).unwrap();
+        assert_eq!(parsed, tree);
+
+        let narrow = TreeFormatter::display(&tree).with_indent(2).to_string();
+        assert_eq!(parse(&narrow).unwrap(), tree);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("a\n\tb\n    c"),
+            Err(ParseIndentedError::BadIndentation { line: 3 })
+        );
+
+        let result = Tree::parse_indented("1\n  2\n  x", |label| label.parse::<i32>());
+        let error = result.unwrap_err();
+        assert_eq!(error.line(), 3);
+        assert_eq!(
+            error.to_string(),
+            "line 3: invalid label: invalid digit found in string"
+        );
+
+        assert!(parse("").unwrap().root_node_id().is_none());
+    }
+}
@@ -0,0 +1,369 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+
+use super::*;
+
+///
+/// The parent, children and (sometimes) data a `Tree` slot held at some other point in time.
+///
+/// `data` is only present when the `Tree` no longer holds it: either the `Node` has since been
+/// removed, or its data has since been replaced.
+///
+struct StoredNode<T> {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    data: Option<T>,
+}
+
+///
+/// Records everything a series of mutations changes in a `Tree` so that it can be reverted.
+///
+/// Before each mutation, the `Changeset` saves the state of every slot the mutation could touch
+/// (the first time that slot is touched), along with enough of `free_ids` to rebuild it.  Reverting
+/// swaps the saved state with the `Tree`'s current state, which leaves the `Changeset` holding the
+/// state that was just undone.  Reverting a second time therefore re-applies the changes, with all
+/// of the same `NodeId`s.
+///
+pub(crate) struct Changeset<T> {
+    root: Option<NodeId>,
+    len: usize,
+    // `free_ids[..free_ids_prefix]` is never changed by the recorded mutations.
+    free_ids_prefix: usize,
+    free_ids_tail: Vec<NodeId>,
+    slots: Vec<(usize, Option<StoredNode<T>>)>,
+    touched: HashMap<usize, usize>,
+}
+
+impl<T> Changeset<T> {
+    pub(crate) fn new(tree: &Tree<T>) -> Changeset<T> {
+        Changeset {
+            root: tree.root.clone(),
+            len: tree.nodes.len(),
+            free_ids_prefix: tree.free_ids.len(),
+            free_ids_tail: Vec::new(),
+            slots: Vec::new(),
+            touched: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    ///
+    /// Swaps the recorded state with the current state of `tree`.
+    ///
+    pub(crate) fn revert(&mut self, tree: &mut Tree<T>) {
+        mem::swap(&mut self.root, &mut tree.root);
+
+        let tail = tree.free_ids.split_off(self.free_ids_prefix);
+        tree.free_ids.append(&mut self.free_ids_tail);
+        self.free_ids_tail = tail;
+
+        let len = tree.nodes.len();
+        while tree.nodes.len() < self.len {
+            tree.nodes.push(None);
+        }
+
+        for &mut (index, ref mut stored) in self.slots.iter_mut() {
+            let slot = &mut tree.nodes[index];
+
+            match (slot.take(), stored.take()) {
+                (Some(mut node), Some(mut other)) => {
+                    mem::swap(&mut node.parent, &mut other.parent);
+                    mem::swap(&mut node.children, &mut other.children);
+                    if let Some(ref mut data) = other.data {
+                        mem::swap(data, &mut node.data);
+                    }
+                    *slot = Some(node);
+                    *stored = Some(other);
+                }
+                (Some(node), None) => {
+                    *stored = Some(StoredNode {
+                        parent: node.parent,
+                        children: node.children,
+                        data: Some(node.data),
+                    });
+                }
+                (None, Some(other)) => {
+                    *slot = Some(Node {
+                        data: other.data.expect(
+                            "Changeset::revert: A removed Node's data wasn't recorded. Please \
+                             report this issue!",
+                        ),
+                        parent: other.parent,
+                        children: other.children,
+                    });
+                }
+                (None, None) => {}
+            }
+        }
+
+        tree.nodes.truncate(self.len);
+        self.len = len;
+
+        tree.notify(|| TreeEvent::Restored);
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        tree: &mut Tree<T>,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        match behavior {
+            InsertBehavior::UnderNode(parent_id) => {
+                tree.get(parent_id)?;
+                self.touch(tree, parent_id);
+            }
+            InsertBehavior::AsRoot => {
+                if let Some(root_id) = tree.root.clone() {
+                    self.touch(tree, &root_id);
+                }
+            }
+        }
+        self.touch_next_slot(tree);
+
+        tree.insert(node, behavior)
+    }
+
+    pub(crate) fn remove_node(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        match behavior {
+            RemoveBehavior::DropChildren => {
+                // Remove the sub-tree one leaf at a time so that every Node's data can be kept.
+                let ids: Vec<NodeId> = tree.traverse_post_order_ids(&node_id)?.collect();
+                for id in ids {
+                    self.remove_one(tree, id, RemoveBehavior::OrphanChildren);
+                }
+            }
+            _ => {
+                tree.get(&node_id)?;
+                self.remove_one(tree, node_id, behavior);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_one(&mut self, tree: &mut Tree<T>, node_id: NodeId, behavior: RemoveBehavior) {
+        self.touch_family(tree, &node_id);
+
+        let index = node_id.index;
+        let node = tree
+            .remove_node(node_id, behavior)
+            .expect("Changeset::remove_one: NodeId was checked before removal.");
+        self.hold_data(index, node.data);
+    }
+
+    pub(crate) fn move_node(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        // every NodeId is checked before anything is touched, so that a failed move records
+        // nothing
+        tree.get(node_id)?;
+        if let MoveBehavior::ToParent(parent_id) = behavior {
+            tree.get(parent_id)?;
+        }
+
+        self.touch_family(tree, node_id);
+        match behavior {
+            MoveBehavior::ToParent(parent_id) => self.touch(tree, parent_id),
+            MoveBehavior::ToRoot => {
+                if let Some(root_id) = tree.root.clone() {
+                    self.touch(tree, &root_id);
+                }
+            }
+        }
+
+        tree.move_node(node_id, behavior)
+    }
+
+    pub(crate) fn swap_nodes(
+        &mut self,
+        tree: &mut Tree<T>,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        tree.get(first_id)?;
+        tree.get(second_id)?;
+        self.touch_family(tree, first_id);
+        self.touch_family(tree, second_id);
+
+        tree.swap_nodes(first_id, second_id, behavior)
+    }
+
+    pub(crate) fn sort_children_by<F>(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+        compare: F,
+    ) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        tree.get(node_id)?;
+        self.touch(tree, node_id);
+
+        tree.sort_children_by(node_id, compare)
+    }
+
+    pub(crate) fn sort_children_by_data(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+    ) -> Result<(), NodeIdError>
+    where
+        T: Ord,
+    {
+        tree.get(node_id)?;
+        self.touch(tree, node_id);
+
+        tree.sort_children_by_data(node_id)
+    }
+
+    pub(crate) fn sort_children_by_key<B, F>(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+        f: F,
+    ) -> Result<(), NodeIdError>
+    where
+        B: Ord,
+        F: FnMut(&Node<T>) -> B,
+    {
+        tree.get(node_id)?;
+        self.touch(tree, node_id);
+
+        tree.sort_children_by_key(node_id, f)
+    }
+
+    pub(crate) fn make_nth_sibling(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+        pos: usize,
+    ) -> Result<(), NodeIdError> {
+        self.touch_parent(tree, node_id)?;
+        tree.make_nth_sibling(node_id, pos)
+    }
+
+    pub(crate) fn make_first_sibling(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+    ) -> Result<bool, NodeIdError> {
+        self.touch_parent(tree, node_id)?;
+        tree.make_first_sibling(node_id)
+    }
+
+    pub(crate) fn make_last_sibling(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+    ) -> Result<bool, NodeIdError> {
+        self.touch_parent(tree, node_id)?;
+        tree.make_last_sibling(node_id)
+    }
+
+    pub(crate) fn replace_data(
+        &mut self,
+        tree: &mut Tree<T>,
+        node_id: &NodeId,
+        data: T,
+    ) -> Result<(), NodeIdError> {
+        tree.get(node_id)?;
+        self.touch(tree, node_id);
+
+        let old = tree.replace_data(node_id, data)?;
+        self.hold_data(node_id.index, old);
+
+        Ok(())
+    }
+
+    fn touch_parent(&mut self, tree: &Tree<T>, node_id: &NodeId) -> Result<(), NodeIdError> {
+        if let Some(parent_id) = tree.get(node_id)?.parent() {
+            self.touch(tree, parent_id);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Touches a `Node` along with its parent and children.
+    ///
+    fn touch_family(&mut self, tree: &Tree<T>, node_id: &NodeId) {
+        self.touch(tree, node_id);
+
+        let node = tree.get_unsafe(node_id);
+        if let Some(parent_id) = node.parent() {
+            self.touch(tree, parent_id);
+        }
+        for child_id in node.children() {
+            self.touch(tree, child_id);
+        }
+    }
+
+    ///
+    /// Touches the slot that the next inserted `Node` will occupy.
+    ///
+    fn touch_next_slot(&mut self, tree: &Tree<T>) {
+        let index = match tree.free_ids.last() {
+            Some(free_id) => {
+                if tree.free_ids.len() == self.free_ids_prefix {
+                    // this free id predates the changeset, so it must be put back on revert
+                    self.free_ids_prefix -= 1;
+                    self.free_ids_tail.insert(0, free_id.clone());
+                }
+                free_id.index
+            }
+            None => tree.nodes.len(),
+        };
+
+        self.touch_index(tree, index);
+    }
+
+    fn touch(&mut self, tree: &Tree<T>, node_id: &NodeId) {
+        self.touch_index(tree, node_id.index);
+    }
+
+    fn touch_index(&mut self, tree: &Tree<T>, index: usize) {
+        if self.touched.contains_key(&index) {
+            return;
+        }
+
+        let stored = tree
+            .nodes
+            .get(index)
+            .and_then(|slot| slot.as_ref())
+            .map(|node| StoredNode {
+                parent: node.parent.clone(),
+                children: node.children.clone(),
+                data: None,
+            });
+
+        self.touched.insert(index, self.slots.len());
+        self.slots.push((index, stored));
+    }
+
+    ///
+    /// Keeps `data` as the recorded data of a touched slot, unless older data is already recorded
+    /// or the slot was empty when it was first touched.
+    ///
+    fn hold_data(&mut self, index: usize, data: T) {
+        let position = self.touched[&index];
+        if let Some(ref mut stored) = self.slots[position].1 {
+            if stored.data.is_none() {
+                stored.data = Some(data);
+            }
+        }
+    }
+}
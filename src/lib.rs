@@ -72,17 +72,60 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(all(test, feature = "serde_support"))]
+extern crate serde_json;
+
+#[macro_use]
+mod macros;
+
 mod behaviors;
+mod binary;
+mod compact;
+mod cursor;
+mod diff;
+mod dot;
 mod error;
+mod formatter;
+mod history;
+mod indented;
 mod iterators;
+mod journal;
+mod linked;
+#[cfg(feature = "serde_support")]
+pub mod nested;
+mod newick;
 mod node;
+mod node_ref;
+mod observer;
+mod parent_links;
+mod sexpr;
+mod transaction;
 mod tree;
 
 pub use behaviors::InsertBehavior;
 pub use behaviors::MoveBehavior;
 pub use behaviors::RemoveBehavior;
 pub use behaviors::SwapBehavior;
+pub use binary::BinaryCodec;
+pub use compact::NodeIdRemap;
+pub use cursor::Cursor;
+pub use cursor::CursorMut;
+pub use diff::apply_patch;
+pub use diff::diff;
+pub use diff::EditOp;
+pub use diff::PatchRef;
+pub use dot::DotOptions;
+pub use error::GetManyMutError;
+pub use error::InsertSiblingError;
+pub use error::InvariantViolation;
+pub use error::NewickError;
 pub use error::NodeIdError;
+pub use error::ParentLinksError;
+pub use error::ParseIndentedError;
+pub use error::SexprError;
+pub use formatter::Glyphs;
+pub use formatter::TreeFormatter;
+pub use history::HistoryTree;
 pub use iterators::AncestorIds;
 pub use iterators::Ancestors;
 pub use iterators::Children;
@@ -93,8 +136,25 @@ pub use iterators::PostOrderTraversal;
 pub use iterators::PostOrderTraversalIds;
 pub use iterators::PreOrderTraversal;
 pub use iterators::PreOrderTraversalIds;
+pub use linked::LinkedAncestorIds;
+pub use linked::LinkedChildrenIds;
+pub use linked::LinkedLevelOrderIds;
+pub use linked::LinkedPostOrderIds;
+pub use linked::LinkedPreOrderIds;
+pub use linked::LinkedTree;
+pub use newick::NewickData;
+pub use newick::NewickNode;
 pub use node::Node;
 pub use node::NodeBuilder;
+pub use node_ref::NodeRef;
+pub use node_ref::NodeRefAncestors;
+pub use node_ref::NodeRefChildren;
+pub use node_ref::NodeRefDescendants;
+pub use observer::ObserverId;
+pub use observer::TreeEvent;
+pub use parent_links::ParentLinks;
+pub use transaction::Transaction;
+pub use tree::MemoryStats;
 pub use tree::Tree;
 pub use tree::TreeBuilder;
 
@@ -0,0 +1,1163 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+///
+/// The links from a `Node` in a `LinkedTree` to its neighbours.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Links {
+    parent: Option<usize>,
+    first_child: Option<usize>,
+    last_child: Option<usize>,
+    prev_sibling: Option<usize>,
+    next_sibling: Option<usize>,
+}
+
+///
+/// A tree stored in first-child/next-sibling form.
+///
+/// Rather than each `Node` owning a `Vec` of its children, every `Node` links directly to its
+/// parent, its first and last children and its previous and next siblings.  Inserting a `Node`
+/// before or after another, detaching one and moving one all take constant time (apart from the
+/// checks `MoveBehavior::ToParent` needs), and no `Node` needs a heap allocation of its own.
+///
+/// The links are kept apart from the data, so walking the structure of the tree (such as with
+/// `LinkedTree::traverse_pre_order_ids`) only touches the compact array of links, however large
+/// the data is.
+///
+/// `LinkedTree` is a separate type with its own, smaller API, not a layout that `Tree` can be
+/// switched to: `Tree` keeps its existing layout, since `Node::children` hands out the `Vec` of
+/// children directly.  A `LinkedTree` is built with `TreeBuilder::build_linked`, and can be turned
+/// into a `Tree` with `LinkedTree::into_tree` to use the rest of `id_tree`'s API.
+///
+/// It has the same traversals over `NodeId`s as `Tree` (`children_ids`, `ancestor_ids` and
+/// `traverse_pre_order_ids`, `traverse_post_order_ids` and `traverse_level_order_ids`).  The ones
+/// that hand out `&Node`s have no counterpart, since a `LinkedTree` doesn't store `Node`s; use
+/// `LinkedTree::get` on the `NodeId`s instead.
+///
+/// As in a `Tree`, only the root and orphaned `Node`s have no parent, and none of them have
+/// siblings.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: LinkedTree<i32> = TreeBuilder::new().build_linked();
+/// let root_id = tree.insert(0, AsRoot).unwrap();
+/// let last_id = tree.insert(3, UnderNode(&root_id)).unwrap();
+/// let first_id = tree.insert_before(1, &last_id).unwrap();
+/// tree.insert_after(2, &first_id).unwrap();
+///
+/// let data: Vec<i32> = tree
+///     .children_ids(&root_id)
+///     .unwrap()
+///     .map(|child_id| *tree.get(&child_id).unwrap())
+///     .collect();
+/// assert_eq!(data, vec![1, 2, 3]);
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct LinkedTree<T> {
+    root: Option<usize>,
+    // `links[i]` and `data[i]` describe the same Node; a slot is in use if its data is there
+    links: Vec<Links>,
+    data: Vec<Option<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> TreeBuilder<T> {
+    ///
+    /// Builds a `LinkedTree` with the settings of this `TreeBuilder`, instead of a `Tree`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: LinkedTree<i32> = TreeBuilder::new()
+    ///     .with_root(Node::new(5))
+    ///     .with_node_capacity(3)
+    ///     .build_linked();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(&root_id).unwrap(), &5);
+    /// ```
+    ///
+    pub fn build_linked(self) -> LinkedTree<T> {
+        let mut tree = LinkedTree {
+            root: None,
+            links: Vec::with_capacity(self.node_capacity),
+            data: Vec::with_capacity(self.node_capacity),
+            free: Vec::with_capacity(self.swap_capacity),
+        };
+
+        if let Some(root) = self.root {
+            tree.insert(root.data, InsertBehavior::AsRoot)
+                .expect("TreeBuilder::build_linked: Inserting a root can't fail.");
+        }
+
+        tree
+    }
+}
+
+impl<T> LinkedTree<T> {
+    ///
+    /// Creates a new, empty `LinkedTree`.
+    ///
+    pub fn new() -> LinkedTree<T> {
+        TreeBuilder::new().build_linked()
+    }
+
+    ///
+    /// Returns the number of `Node`s in the `LinkedTree`, including any that have been orphaned.
+    ///
+    pub fn len(&self) -> usize {
+        self.data.len() - self.free.len()
+    }
+
+    ///
+    /// Returns `true` if the `LinkedTree` has no `Node`s at all.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// Returns the `NodeId` of the root `Node`, if there is one.
+    ///
+    pub fn root_node_id(&self) -> Option<NodeId> {
+        self.root.map(|index| NodeId { index })
+    }
+
+    ///
+    /// Returns a reference to the data of the given `Node`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn get(&self, node_id: &NodeId) -> Result<&T, NodeIdError> {
+        match self.data.get(node_id.index) {
+            Some(Some(data)) => Ok(data),
+            _ => Err(NodeIdError::NodeIdNoLongerValid),
+        }
+    }
+
+    ///
+    /// Returns a mutable reference to the data of the given `Node`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn get_mut(&mut self, node_id: &NodeId) -> Result<&mut T, NodeIdError> {
+        match self.data.get_mut(node_id.index) {
+            Some(Some(data)) => Ok(data),
+            _ => Err(NodeIdError::NodeIdNoLongerValid),
+        }
+    }
+
+    ///
+    /// Returns the parent of the given `Node`, if it has one.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn parent(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        self.link(node_id, |links| links.parent)
+    }
+
+    ///
+    /// Returns the first child of the given `Node`, if it has any.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn first_child(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        self.link(node_id, |links| links.first_child)
+    }
+
+    ///
+    /// Returns the last child of the given `Node`, if it has any.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn last_child(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        self.link(node_id, |links| links.last_child)
+    }
+
+    ///
+    /// Returns the sibling just before the given `Node`, if there is one.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn prev_sibling(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        self.link(node_id, |links| links.prev_sibling)
+    }
+
+    ///
+    /// Returns the sibling just after the given `Node`, if there is one.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn next_sibling(&self, node_id: &NodeId) -> Result<Option<NodeId>, NodeIdError> {
+        self.link(node_id, |links| links.next_sibling)
+    }
+
+    ///
+    /// Returns an `Iterator` over the `NodeId`s of the children of the given `Node`, in order.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn children_ids(&self, node_id: &NodeId) -> Result<LinkedChildrenIds<'_, T>, NodeIdError> {
+        let next = self.first_child(node_id)?.map(|child_id| child_id.index);
+        Ok(LinkedChildrenIds { tree: self, next })
+    }
+
+    ///
+    /// Returns an `Iterator` over the `NodeId`s in the sub-tree of the given `Node`, in pre-order.
+    ///
+    /// The traversal follows the links between `Node`s, so it needs no extra storage.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let child_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    /// tree.insert(2, UnderNode(&child_id)).unwrap();
+    /// tree.insert(3, UnderNode(&root_id)).unwrap();
+    ///
+    /// let data: Vec<i32> = tree
+    ///     .traverse_pre_order_ids(&root_id)
+    ///     .unwrap()
+    ///     .map(|node_id| *tree.get(&node_id).unwrap())
+    ///     .collect();
+    /// assert_eq!(data, vec![0, 1, 2, 3]);
+    /// ```
+    ///
+    pub fn traverse_pre_order_ids(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<LinkedPreOrderIds<'_, T>, NodeIdError> {
+        self.check(node_id)?;
+        Ok(LinkedPreOrderIds {
+            tree: self,
+            start: node_id.index,
+            next: Some(node_id.index),
+        })
+    }
+
+    ///
+    /// Returns an `Iterator` over the `NodeId`s of the ancestors of the given `Node`, starting
+    /// with its parent.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let child_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(2, UnderNode(&child_id)).unwrap();
+    ///
+    /// let ancestors: Vec<NodeId> = tree.ancestor_ids(&grandchild_id).unwrap().collect();
+    /// assert_eq!(ancestors, vec![child_id, root_id]);
+    /// ```
+    ///
+    pub fn ancestor_ids(&self, node_id: &NodeId) -> Result<LinkedAncestorIds<'_, T>, NodeIdError> {
+        let next = self.parent(node_id)?.map(|parent_id| parent_id.index);
+        Ok(LinkedAncestorIds { tree: self, next })
+    }
+
+    ///
+    /// Returns an `Iterator` over the `NodeId`s in the sub-tree of the given `Node`, in
+    /// post-order.
+    ///
+    /// Like `LinkedTree::traverse_pre_order_ids`, the traversal follows the links between `Node`s,
+    /// so it needs no extra storage.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let child_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    /// tree.insert(2, UnderNode(&child_id)).unwrap();
+    /// tree.insert(3, UnderNode(&root_id)).unwrap();
+    ///
+    /// let data: Vec<i32> = tree
+    ///     .traverse_post_order_ids(&root_id)
+    ///     .unwrap()
+    ///     .map(|node_id| *tree.get(&node_id).unwrap())
+    ///     .collect();
+    /// assert_eq!(data, vec![2, 1, 3, 0]);
+    /// ```
+    ///
+    pub fn traverse_post_order_ids(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<LinkedPostOrderIds<'_, T>, NodeIdError> {
+        self.check(node_id)?;
+        Ok(LinkedPostOrderIds {
+            tree: self,
+            start: node_id.index,
+            next: Some(self.first_leaf(node_id.index)),
+        })
+    }
+
+    ///
+    /// Returns an `Iterator` over the `NodeId`s in the sub-tree of the given `Node`, in
+    /// level-order.
+    ///
+    /// Unlike the other traversals, this one keeps a queue of the `Node`s whose children are still
+    /// to come.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let child_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    /// tree.insert(2, UnderNode(&child_id)).unwrap();
+    /// tree.insert(3, UnderNode(&root_id)).unwrap();
+    ///
+    /// let data: Vec<i32> = tree
+    ///     .traverse_level_order_ids(&root_id)
+    ///     .unwrap()
+    ///     .map(|node_id| *tree.get(&node_id).unwrap())
+    ///     .collect();
+    /// assert_eq!(data, vec![0, 1, 3, 2]);
+    /// ```
+    ///
+    pub fn traverse_level_order_ids(
+        &self,
+        node_id: &NodeId,
+    ) -> Result<LinkedLevelOrderIds<'_, T>, NodeIdError> {
+        self.check(node_id)?;
+        let mut queue = VecDeque::new();
+        queue.push_back(node_id.index);
+        Ok(LinkedLevelOrderIds { tree: self, queue })
+    }
+
+    ///
+    /// Inserts a new `Node` holding `data`.
+    ///
+    /// `InsertBehavior::UnderNode` adds it as the last child of the given `Node`, while
+    /// `InsertBehavior::AsRoot` makes it the root, with any previous root as its child.
+    ///
+    /// Returns a `NodeIdError` if the parent's `NodeId` is no longer valid.
+    ///
+    pub fn insert(&mut self, data: T, behavior: InsertBehavior) -> Result<NodeId, NodeIdError> {
+        let parent = match behavior {
+            InsertBehavior::UnderNode(parent_id) => {
+                self.check(parent_id)?;
+                Some(parent_id.index)
+            }
+            InsertBehavior::AsRoot => None,
+        };
+
+        let index = self.new_slot(data);
+        match parent {
+            Some(parent) => self.append_child(parent, index),
+            None => {
+                if let Some(old_root) = self.root {
+                    self.append_child(index, old_root);
+                }
+                self.root = Some(index);
+            }
+        }
+
+        Ok(NodeId { index })
+    }
+
+    ///
+    /// Inserts a new `Node` holding `data` just before `sibling`, under the same parent.
+    ///
+    /// Returns an `InsertSiblingError` if the sibling's `NodeId` is no longer valid, or if the
+    /// sibling has no parent (so that the root never has siblings).
+    ///
+    pub fn insert_before(
+        &mut self,
+        data: T,
+        sibling: &NodeId,
+    ) -> Result<NodeId, InsertSiblingError> {
+        self.check_sibling(sibling)?;
+        let index = self.new_slot(data);
+        self.link_beside(sibling.index, index, false);
+        Ok(NodeId { index })
+    }
+
+    ///
+    /// Inserts a new `Node` holding `data` just after `sibling`, under the same parent.
+    ///
+    /// Returns an `InsertSiblingError` if the sibling's `NodeId` is no longer valid, or if the
+    /// sibling has no parent (so that the root never has siblings).
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let child_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    ///
+    /// assert!(tree.insert_after(2, &child_id).is_ok());
+    /// assert_eq!(
+    ///     tree.insert_after(3, &root_id),
+    ///     Err(InsertSiblingError::NoParent)
+    /// );
+    /// ```
+    ///
+    pub fn insert_after(
+        &mut self,
+        data: T,
+        sibling: &NodeId,
+    ) -> Result<NodeId, InsertSiblingError> {
+        self.check_sibling(sibling)?;
+        let index = self.new_slot(data);
+        self.link_beside(sibling.index, index, true);
+        Ok(NodeId { index })
+    }
+
+    ///
+    /// Moves a `Node` (along with its sub-tree) the same way `Tree::move_node` does.  Moving the
+    /// root below an orphaned `Node` leaves the `LinkedTree` without a root.
+    ///
+    /// Returns a `NodeIdError` if either `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    ///
+    /// let mut tree: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = tree.insert(0, AsRoot).unwrap();
+    /// let first_id = tree.insert(1, UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(2, UnderNode(&root_id)).unwrap();
+    ///
+    /// tree.move_node(&first_id, ToParent(&second_id)).unwrap();
+    /// assert_eq!(tree.first_child(&root_id).unwrap(), Some(second_id.clone()));
+    /// assert_eq!(tree.parent(&first_id).unwrap(), Some(second_id));
+    /// ```
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.check(node_id)?;
+        let index = node_id.index;
+
+        match behavior {
+            MoveBehavior::ToRoot => {
+                if self.root == Some(index) {
+                    return Ok(());
+                }
+                self.unlink(index);
+                if let Some(old_root) = self.root {
+                    self.append_child(index, old_root);
+                }
+                self.root = Some(index);
+            }
+            MoveBehavior::ToParent(parent_id) => {
+                self.check(parent_id)?;
+                let parent = parent_id.index;
+                if parent == index {
+                    return Ok(());
+                }
+
+                // when moving a Node under its own descendant, the child on the path between them
+                // takes the Node's place
+                if let Some(between) = self.child_on_path(index, parent) {
+                    let old_parent = self.links(index).parent;
+                    self.unlink(between);
+                    self.unlink(index);
+                    match old_parent {
+                        Some(old_parent) => self.append_child(old_parent, between),
+                        None if self.root == Some(index) => self.root = Some(between),
+                        None => {}
+                    }
+                } else {
+                    // the root can only end up below a Node outside its own sub-tree by way of an
+                    // orphan, and then the LinkedTree is left without one
+                    if self.root == Some(index) {
+                        self.root = None;
+                    }
+                    self.unlink(index);
+                }
+                self.append_child(parent, index);
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Removes a `Node` the same way `Tree::remove_node` does, returning its data.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<T, NodeIdError> {
+        self.check(&node_id)?;
+        let index = node_id.index;
+        let parent = self.links(index).parent;
+        self.unlink(index);
+        if self.root == Some(index) {
+            self.root = None;
+        }
+
+        match behavior {
+            RemoveBehavior::DropChildren => {
+                let mut descendants = Vec::new();
+                let mut next = self.links(index).first_child;
+                while let Some(child) = next {
+                    next = self.links(child).next_sibling;
+                    descendants.push(child);
+                }
+                while let Some(descendant) = descendants.pop() {
+                    let mut next = self.links(descendant).first_child;
+                    while let Some(child) = next {
+                        next = self.links(child).next_sibling;
+                        descendants.push(child);
+                    }
+                    self.free_slot(descendant);
+                }
+            }
+            RemoveBehavior::LiftChildren | RemoveBehavior::OrphanChildren => {
+                let lift_to = match behavior {
+                    RemoveBehavior::LiftChildren => parent,
+                    _ => None,
+                };
+                let mut next = self.links(index).first_child;
+                while let Some(child) = next {
+                    let links = self.links(child);
+                    next = links.next_sibling;
+                    *self.links_mut(child) = Links {
+                        first_child: links.first_child,
+                        last_child: links.last_child,
+                        ..Links::default()
+                    };
+                    if let Some(lift_to) = lift_to {
+                        self.append_child(lift_to, child);
+                    }
+                }
+            }
+        }
+
+        Ok(self.free_slot(index))
+    }
+
+    ///
+    /// Turns the `LinkedTree` into a `Tree`, with the root's sub-tree followed by the sub-trees of
+    /// any orphaned `Node`s, each in pre-order.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut linked: LinkedTree<i32> = LinkedTree::new();
+    /// let root_id = linked.insert(0, AsRoot).unwrap();
+    /// linked.insert(1, UnderNode(&root_id)).unwrap();
+    ///
+    /// let tree = linked.into_tree();
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(root_id).unwrap().children().len(), 1);
+    /// ```
+    ///
+    pub fn into_tree(mut self) -> Tree<T> {
+        let parentless = (0..self.data.len()).filter(|&index| {
+            self.data[index].is_some()
+                && self.links[index].parent.is_none()
+                && Some(index) != self.root
+        });
+        let tops: Vec<usize> = self.root.into_iter().chain(parentless).collect();
+
+        let mut visited = vec![false; self.data.len()];
+        let mut order = Vec::with_capacity(self.len());
+        for top in tops {
+            if visited[top] {
+                continue;
+            }
+            let sub_tree = LinkedPreOrderIds {
+                tree: &self,
+                start: top,
+                next: Some(top),
+            };
+            for node_id in sub_tree {
+                if !visited[node_id.index] {
+                    visited[node_id.index] = true;
+                    order.push(node_id.index);
+                }
+            }
+        }
+
+        let mut tree = TreeBuilder::new().with_node_capacity(order.len()).build();
+        let mut new_ids: Vec<Option<NodeId>> = vec![None; self.data.len()];
+        for index in order {
+            let data = self.data[index]
+                .take()
+                .expect("LinkedTree::into_tree: Every Node is visited once.");
+            let new_id = NodeId {
+                index: tree.nodes.len(),
+            };
+            // parents come before their children in pre-order
+            let parent = self.links[index]
+                .parent
+                .and_then(|parent| new_ids[parent].clone());
+            if let Some(ref parent_id) = parent {
+                tree.get_mut_unsafe(parent_id).add_child(new_id.clone());
+            }
+            tree.nodes.push(Some(Node {
+                data,
+                parent,
+                children: Vec::new(),
+            }));
+            new_ids[index] = Some(new_id);
+        }
+        tree.root = self.root.and_then(|root| new_ids[root].clone());
+
+        tree
+    }
+
+    fn check(&self, node_id: &NodeId) -> Result<(), NodeIdError> {
+        self.get(node_id).map(|_| ())
+    }
+
+    fn check_sibling(&self, sibling: &NodeId) -> Result<(), InsertSiblingError> {
+        self.check(sibling)
+            .map_err(|_| InsertSiblingError::NodeIdNoLongerValid)?;
+        match self.links(sibling.index).parent {
+            Some(_) => Ok(()),
+            None => Err(InsertSiblingError::NoParent),
+        }
+    }
+
+    fn links(&self, index: usize) -> Links {
+        self.links[index]
+    }
+
+    fn links_mut(&mut self, index: usize) -> &mut Links {
+        &mut self.links[index]
+    }
+
+    fn link<F>(&self, node_id: &NodeId, f: F) -> Result<Option<NodeId>, NodeIdError>
+    where
+        F: FnOnce(&Links) -> Option<usize>,
+    {
+        self.check(node_id)?;
+        Ok(f(&self.links[node_id.index]).map(|index| NodeId { index }))
+    }
+
+    fn new_slot(&mut self, data: T) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.data[index] = Some(data);
+                index
+            }
+            None => {
+                self.links.push(Links::default());
+                self.data.push(Some(data));
+                self.data.len() - 1
+            }
+        }
+    }
+
+    fn free_slot(&mut self, index: usize) -> T {
+        self.free.push(index);
+        self.links[index] = Links::default();
+        self.data[index]
+            .take()
+            .expect("LinkedTree::free_slot: Only Nodes in the LinkedTree are freed.")
+    }
+
+    ///
+    /// Links the `Node` at `index` in as the last child of `parent`, first detaching it from
+    /// wherever it was.
+    ///
+    fn append_child(&mut self, parent: usize, index: usize) {
+        self.unlink(index);
+        let last = self.links(parent).last_child;
+        match last {
+            Some(last) => self.links_mut(last).next_sibling = Some(index),
+            None => self.links_mut(parent).first_child = Some(index),
+        }
+        self.links_mut(parent).last_child = Some(index);
+
+        let links = self.links_mut(index);
+        links.parent = Some(parent);
+        links.prev_sibling = last;
+    }
+
+    ///
+    /// Links the unlinked `Node` at `index` in just before or after `sibling`, which must have a
+    /// parent.
+    ///
+    fn link_beside(&mut self, sibling: usize, index: usize, after: bool) {
+        let sibling_links = self.links(sibling);
+        let (prev, next) = if after {
+            (Some(sibling), sibling_links.next_sibling)
+        } else {
+            (sibling_links.prev_sibling, Some(sibling))
+        };
+
+        {
+            let links = self.links_mut(index);
+            links.parent = sibling_links.parent;
+            links.prev_sibling = prev;
+            links.next_sibling = next;
+        }
+        match prev {
+            Some(prev) => self.links_mut(prev).next_sibling = Some(index),
+            None => {
+                if let Some(parent) = sibling_links.parent {
+                    self.links_mut(parent).first_child = Some(index);
+                }
+            }
+        }
+        match next {
+            Some(next) => self.links_mut(next).prev_sibling = Some(index),
+            None => {
+                if let Some(parent) = sibling_links.parent {
+                    self.links_mut(parent).last_child = Some(index);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Detaches the `Node` at `index` from its parent and siblings, keeping its children.
+    ///
+    fn unlink(&mut self, index: usize) {
+        let links = self.links(index);
+        match links.prev_sibling {
+            Some(prev) => self.links_mut(prev).next_sibling = links.next_sibling,
+            None => {
+                if let Some(parent) = links.parent {
+                    self.links_mut(parent).first_child = links.next_sibling;
+                }
+            }
+        }
+        match links.next_sibling {
+            Some(next) => self.links_mut(next).prev_sibling = links.prev_sibling,
+            None => {
+                if let Some(parent) = links.parent {
+                    self.links_mut(parent).last_child = links.prev_sibling;
+                }
+            }
+        }
+
+        let links = self.links_mut(index);
+        links.parent = None;
+        links.prev_sibling = None;
+        links.next_sibling = None;
+    }
+
+    ///
+    /// If `descendant` is below `ancestor`, returns the child of `ancestor` on the path between
+    /// them.
+    ///
+    fn child_on_path(&self, ancestor: usize, descendant: usize) -> Option<usize> {
+        let mut current = descendant;
+        while let Some(parent) = self.links(current).parent {
+            if parent == ancestor {
+                return Some(current);
+            }
+            current = parent;
+        }
+        None
+    }
+
+    ///
+    /// Returns the first `Node` in a post-order traversal of the sub-tree of `index`, found by
+    /// following first children down to a leaf.
+    ///
+    fn first_leaf(&self, mut index: usize) -> usize {
+        while let Some(first_child) = self.links(index).first_child {
+            index = first_child;
+        }
+        index
+    }
+
+    ///
+    /// Returns the `Node` after `index` in a pre-order traversal of the sub-tree of `start`.
+    ///
+    fn pre_order_successor(&self, start: usize, index: usize, links: &Links) -> Option<usize> {
+        if links.first_child.is_some() {
+            return links.first_child;
+        }
+
+        let mut current = index;
+        let mut links = *links;
+        while current != start {
+            if links.next_sibling.is_some() {
+                return links.next_sibling;
+            }
+            current = links.parent?;
+            links = self.links(current);
+        }
+        None
+    }
+}
+
+impl<T> Default for LinkedTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s of the children of a `Node` in a `LinkedTree`.
+///
+pub struct LinkedChildrenIds<'a, T: 'a> {
+    tree: &'a LinkedTree<T>,
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for LinkedChildrenIds<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let index = self.next?;
+        self.next = self.tree.links(index).next_sibling;
+        Some(NodeId { index })
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s in the sub-tree of a `Node` in a `LinkedTree`, in pre-order.
+///
+pub struct LinkedPreOrderIds<'a, T: 'a> {
+    tree: &'a LinkedTree<T>,
+    start: usize,
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for LinkedPreOrderIds<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let index = self.next?;
+        let links = self.tree.links(index);
+        self.next = self.tree.pre_order_successor(self.start, index, &links);
+        Some(NodeId { index })
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s of the ancestors of a `Node` in a `LinkedTree`.
+///
+pub struct LinkedAncestorIds<'a, T: 'a> {
+    tree: &'a LinkedTree<T>,
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for LinkedAncestorIds<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let index = self.next?;
+        self.next = self.tree.links(index).parent;
+        Some(NodeId { index })
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s in the sub-tree of a `Node` in a `LinkedTree`, in post-order.
+///
+pub struct LinkedPostOrderIds<'a, T: 'a> {
+    tree: &'a LinkedTree<T>,
+    start: usize,
+    next: Option<usize>,
+}
+
+impl<'a, T> Iterator for LinkedPostOrderIds<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let index = self.next?;
+        let links = self.tree.links(index);
+        // after a Node come the leftmost leaf of its next sibling's sub-tree, or else its parent
+        self.next = match (index == self.start, links.next_sibling) {
+            (true, _) => None,
+            (false, Some(next_sibling)) => Some(self.tree.first_leaf(next_sibling)),
+            (false, None) => links.parent,
+        };
+        Some(NodeId { index })
+    }
+}
+
+///
+/// An Iterator over the `NodeId`s in the sub-tree of a `Node` in a `LinkedTree`, in level-order.
+///
+pub struct LinkedLevelOrderIds<'a, T: 'a> {
+    tree: &'a LinkedTree<T>,
+    queue: VecDeque<usize>,
+}
+
+impl<'a, T> Iterator for LinkedLevelOrderIds<'a, T> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let index = self.queue.pop_front()?;
+        let mut child = self.tree.links(index).first_child;
+        while let Some(index) = child {
+            self.queue.push_back(index);
+            child = self.tree.links(index).next_sibling;
+        }
+        Some(NodeId { index })
+    }
+}
+
+#[cfg(test)]
+mod linked_tree_tests {
+    use super::super::*;
+
+    fn data(tree: &LinkedTree<i32>, node_id: &NodeId) -> Vec<i32> {
+        tree.traverse_pre_order_ids(node_id)
+            .unwrap()
+            .map(|node_id| *tree.get(&node_id).unwrap())
+            .collect()
+    }
+
+    fn children(tree: &LinkedTree<i32>, node_id: &NodeId) -> Vec<i32> {
+        tree.children_ids(node_id)
+            .unwrap()
+            .map(|node_id| *tree.get(&node_id).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_siblings() {
+        use InsertBehavior::*;
+
+        let mut tree = LinkedTree::new();
+        let root_id = tree.insert(0, AsRoot).unwrap();
+        let node_1 = tree.insert(1, UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(2, UnderNode(&root_id)).unwrap();
+        tree.insert(3, UnderNode(&node_1)).unwrap();
+        tree.insert_before(-1, &node_1).unwrap();
+        tree.insert_after(4, &node_2).unwrap();
+        let middle = tree.insert_after(5, &node_1).unwrap();
+        assert_eq!(children(&tree, &root_id), vec![-1, 1, 5, 2, 4]);
+        assert_eq!(tree.prev_sibling(&middle).unwrap(), Some(node_1.clone()));
+        assert_eq!(tree.next_sibling(&middle).unwrap(), Some(node_2.clone()));
+
+        let new_root = tree.insert(9, AsRoot).unwrap();
+        assert_eq!(data(&tree, &new_root), vec![9, 0, -1, 1, 3, 5, 2, 4]);
+        assert_eq!(tree.len(), 8);
+
+        // the root, and any orphaned Node, can't have siblings
+        assert_eq!(
+            tree.insert_before(10, &new_root),
+            Err(InsertSiblingError::NoParent)
+        );
+        assert_eq!(
+            tree.insert_after(10, &new_root),
+            Err(InsertSiblingError::NoParent)
+        );
+        tree.remove_node(middle.clone(), RemoveBehavior::DropChildren)
+            .unwrap();
+        assert_eq!(
+            tree.insert_after(10, &middle),
+            Err(InsertSiblingError::NodeIdNoLongerValid)
+        );
+        assert_eq!(tree.len(), 7);
+
+        // so a new root always takes the old one as its only child
+        let newer_root = tree.insert(11, AsRoot).unwrap();
+        tree.move_node(&node_2, MoveBehavior::ToRoot).unwrap();
+        assert_eq!(children(&tree, &node_2), vec![11]);
+        assert_eq!(children(&tree, &newer_root), vec![9]);
+        assert_eq!(tree.next_sibling(&newer_root).unwrap(), None);
+        assert_eq!(data(&tree, &node_2), vec![2, 11, 9, 0, -1, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_move_node() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+
+        let mut tree = LinkedTree::new();
+        let root_id = tree.insert(0, AsRoot).unwrap();
+        let node_1 = tree.insert(1, UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(2, UnderNode(&root_id)).unwrap();
+        let node_3 = tree.insert(3, UnderNode(&node_1)).unwrap();
+
+        // down the tree: 1 takes 0's place as root
+        tree.move_node(&root_id, ToParent(&node_3)).unwrap();
+        assert_eq!(tree.root_node_id(), Some(node_1.clone()));
+        assert_eq!(data(&tree, &node_1), vec![1, 3, 0, 2]);
+
+        tree.move_node(&node_2, ToRoot).unwrap();
+        assert_eq!(data(&tree, &node_2), vec![2, 1, 3, 0]);
+        assert_eq!(tree.last_child(&node_3).unwrap(), Some(root_id.clone()));
+
+        // across the tree
+        tree.move_node(&root_id, ToParent(&node_2)).unwrap();
+        assert_eq!(children(&tree, &node_2), vec![1, 0]);
+        assert_eq!(tree.first_child(&node_3).unwrap(), None);
+
+        tree.move_node(&node_2, ToRoot).unwrap();
+        tree.move_node(&root_id, ToParent(&root_id)).unwrap();
+        assert_eq!(data(&tree, &node_2), vec![2, 1, 3, 0]);
+    }
+
+    #[test]
+    fn test_remove_node() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = LinkedTree::new();
+        let root_id = tree.insert(0, AsRoot).unwrap();
+        let node_1 = tree.insert(1, UnderNode(&root_id)).unwrap();
+        tree.insert(2, UnderNode(&root_id)).unwrap();
+        tree.insert(3, UnderNode(&node_1)).unwrap();
+        assert_eq!(tree.remove_node(node_1.clone(), LiftChildren), Ok(1));
+        assert_eq!(children(&tree, &root_id), vec![2, 3]);
+        assert!(tree.get(&node_1).is_err());
+
+        let mut tree = LinkedTree::new();
+        let root_id = tree.insert(0, AsRoot).unwrap();
+        let node_1 = tree.insert(1, UnderNode(&root_id)).unwrap();
+        tree.insert(2, UnderNode(&root_id)).unwrap();
+        let node_3 = tree.insert(3, UnderNode(&node_1)).unwrap();
+        assert_eq!(tree.remove_node(node_1, OrphanChildren), Ok(1));
+        assert_eq!(children(&tree, &root_id), vec![2]);
+        assert_eq!(tree.parent(&node_3).unwrap(), None);
+        assert_eq!(tree.next_sibling(&node_3).unwrap(), None);
+
+        let mut tree = LinkedTree::new();
+        let root_id = tree.insert(0, AsRoot).unwrap();
+        let node_1 = tree.insert(1, UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(2, UnderNode(&root_id)).unwrap();
+        let node_3 = tree.insert(3, UnderNode(&node_1)).unwrap();
+        assert_eq!(tree.remove_node(root_id.clone(), DropChildren), Ok(0));
+        assert!(tree.is_empty());
+        assert_eq!(tree.root_node_id(), None);
+        assert!([root_id, node_1, node_2, node_3]
+            .iter()
+            .all(|node_id| tree.get(node_id).is_err()));
+
+        // freed slots are reused
+        let root_id = tree.insert(7, AsRoot).unwrap();
+        assert!(root_id.index < 4);
+        assert_eq!(tree.data.len(), 4);
+    }
+
+    #[test]
+    fn test_into_tree() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut linked = LinkedTree::new();
+        let root_id = linked.insert(0, AsRoot).unwrap();
+        let node_1 = linked.insert(1, UnderNode(&root_id)).unwrap();
+        let node_2 = linked.insert(2, UnderNode(&root_id)).unwrap();
+        linked.insert(3, UnderNode(&node_1)).unwrap();
+        *linked.get_mut(&node_2).unwrap() = 20;
+        linked.remove_node(node_1.clone(), OrphanChildren).unwrap();
+
+        let tree = linked.into_tree();
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.len(), 3);
+
+        let root_id = tree.root_node_id().unwrap();
+        let data: Vec<i32> = tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .map(|node| *node.data())
+            .collect();
+        assert_eq!(data, vec![0, 20]);
+        assert_eq!(tree.get(&NodeId { index: 2 }).unwrap().data(), &3);
+    }
+
+    #[test]
+    fn test_traversals() {
+        use InsertBehavior::*;
+
+        let mut linked = LinkedTree::new();
+        let root_id = linked.insert(0, AsRoot).unwrap();
+        let node_1 = linked.insert(1, UnderNode(&root_id)).unwrap();
+        let node_2 = linked.insert(2, UnderNode(&root_id)).unwrap();
+        let node_3 = linked.insert(3, UnderNode(&node_1)).unwrap();
+        linked.insert(4, UnderNode(&node_1)).unwrap();
+        linked.insert(5, UnderNode(&node_2)).unwrap();
+        let node_6 = linked.insert(6, UnderNode(&node_3)).unwrap();
+
+        let to_data = |ids: Vec<NodeId>| -> Vec<i32> {
+            ids.iter().map(|id| *linked.get(id).unwrap()).collect()
+        };
+        let post_order = to_data(linked.traverse_post_order_ids(&root_id).unwrap().collect());
+        let level_order = to_data(linked.traverse_level_order_ids(&root_id).unwrap().collect());
+        assert_eq!(post_order, vec![6, 3, 4, 1, 5, 2, 0]);
+        assert_eq!(level_order, vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            to_data(linked.ancestor_ids(&node_6).unwrap().collect()),
+            vec![3, 1, 0]
+        );
+
+        // a traversal stays within the sub-tree it starts at
+        assert_eq!(
+            to_data(linked.traverse_post_order_ids(&node_1).unwrap().collect()),
+            vec![6, 3, 4, 1]
+        );
+        assert_eq!(
+            to_data(linked.traverse_level_order_ids(&node_1).unwrap().collect()),
+            vec![1, 3, 4, 6]
+        );
+        assert_eq!(
+            to_data(linked.traverse_post_order_ids(&node_6).unwrap().collect()),
+            vec![6]
+        );
+        assert_eq!(linked.ancestor_ids(&root_id).unwrap().count(), 0);
+
+        // and they agree with Tree's
+        let tree = linked.into_tree();
+        let root_id = tree.root_node_id().unwrap();
+        let tree_data = |ids: Vec<NodeId>| -> Vec<i32> {
+            ids.iter().map(|id| *tree.get(id).unwrap().data()).collect()
+        };
+        assert_eq!(
+            tree_data(tree.traverse_post_order_ids(root_id).unwrap().collect()),
+            post_order
+        );
+        assert_eq!(
+            tree_data(tree.traverse_level_order_ids(root_id).unwrap().collect()),
+            level_order
+        );
+    }
+
+    #[test]
+    fn test_root_below_orphan() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut linked = LinkedTree::new();
+        let root_id = linked.insert(0, AsRoot).unwrap();
+        let node_1 = linked.insert(1, UnderNode(&root_id)).unwrap();
+        let orphan = linked.insert(2, UnderNode(&node_1)).unwrap();
+        linked.remove_node(node_1, OrphanChildren).unwrap();
+
+        linked.move_node(&root_id, ToParent(&orphan)).unwrap();
+        assert_eq!(linked.root_node_id(), None);
+        assert_eq!(linked.parent(&root_id).unwrap(), Some(orphan.clone()));
+
+        let tree = linked.into_tree();
+        assert!(tree.validate().is_ok());
+        assert_eq!(tree.root_node_id(), None);
+        assert_eq!(tree.len(), 2);
+        let orphan = NodeId { index: 0 };
+        assert_eq!(tree.get(&orphan).unwrap().data(), &2);
+        assert_eq!(tree.get(&orphan).unwrap().children().len(), 1);
+    }
+}
@@ -0,0 +1,193 @@
+///
+/// Builds a `Tree` from a literal description of its shape.
+///
+/// Each `Node` is written as its data, optionally followed by `=>` and a bracketed list of its
+/// children.  The `Tree` is allocated with room for exactly as many `Node`s as are listed, and
+/// every `Node` with room for exactly its own children.
+///
+/// ```
+/// #[macro_use]
+/// extern crate id_tree;
+///
+/// use id_tree::*;
+///
+/// # fn main() {
+/// //      0
+/// //     / \
+/// //    1   2
+/// //   / \
+/// //  3   4
+/// let tree: Tree<i32> = tree!(0 => [1 => [3, 4], 2]);
+///
+/// let root_id = tree.root_node_id().unwrap();
+/// let data: Vec<i32> = tree
+///     .traverse_pre_order(root_id)
+///     .unwrap()
+///     .map(|node| *node.data())
+///     .collect();
+/// assert_eq!(data, vec![0, 1, 3, 4, 2]);
+/// assert_eq!(tree.capacity(), 5);
+/// # }
+/// ```
+///
+/// Used as a statement starting with `let`, the `Tree` is bound to the given pattern, and any
+/// `Node` written as `name @ data` has its `NodeId` bound to `name` as well.
+///
+/// ```
+/// #[macro_use]
+/// extern crate id_tree;
+///
+/// use id_tree::*;
+///
+/// # fn main() {
+/// tree!(let mut tree = root @ "a" => [b @ "b" => ["c"], "d"]);
+///
+/// assert_eq!(tree.root_node_id(), Some(&root));
+/// assert_eq!(tree.get(&b).unwrap().children().len(), 1);
+/// tree.insert(Node::new("e"), InsertBehavior::UnderNode(&b)).unwrap();
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! tree {
+    (@root $tree:ident $name:ident @ $data:expr $(=> [$($children:tt)*])? $(,)?) => {
+        let $name = $tree
+            .insert(
+                $crate::tree!(@node $data $(, $($children)*)?),
+                $crate::InsertBehavior::AsRoot,
+            )
+            .expect("tree!: Inserting a root never fails.");
+        $($crate::tree!(@nodes $tree $name $($children)*);)?
+    };
+    (@root $tree:ident $data:expr $(=> [$($children:tt)*])? $(,)?) => {
+        let _root_id = $tree
+            .insert(
+                $crate::tree!(@node $data $(, $($children)*)?),
+                $crate::InsertBehavior::AsRoot,
+            )
+            .expect("tree!: Inserting a root never fails.");
+        $($crate::tree!(@nodes $tree _root_id $($children)*);)?
+    };
+
+    (@nodes $tree:ident $parent:ident) => {};
+    (@nodes $tree:ident $parent:ident
+        $name:ident @ $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?
+    ) => {
+        let $name = $tree
+            .insert(
+                $crate::tree!(@node $data $(, $($children)*)?),
+                $crate::InsertBehavior::UnderNode(&$parent),
+            )
+            .expect("tree!: The parent was just inserted.");
+        $($crate::tree!(@nodes $tree $name $($children)*);)?
+        $crate::tree!(@nodes $tree $parent $($($rest)*)?);
+    };
+    (@nodes $tree:ident $parent:ident
+        $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?
+    ) => {
+        let _node_id = $tree
+            .insert(
+                $crate::tree!(@node $data $(, $($children)*)?),
+                $crate::InsertBehavior::UnderNode(&$parent),
+            )
+            .expect("tree!: The parent was just inserted.");
+        $($crate::tree!(@nodes $tree _node_id $($children)*);)?
+        $crate::tree!(@nodes $tree $parent $($($rest)*)?);
+    };
+
+    (@node $data:expr) => {
+        $crate::Node::new($data)
+    };
+    (@node $data:expr, $($children:tt)*) => {
+        $crate::NodeBuilder::new($data)
+            .with_child_capacity($crate::tree!(@len $($children)*))
+            .build()
+    };
+
+    // the number of Nodes in a list, counting their descendants
+    (@count) => { 0usize };
+    (@count $name:ident @ $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?) => {
+        1usize $(+ $crate::tree!(@count $($children)*))? + $crate::tree!(@count $($($rest)*)?)
+    };
+    (@count $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?) => {
+        1usize $(+ $crate::tree!(@count $($children)*))? + $crate::tree!(@count $($($rest)*)?)
+    };
+
+    // the number of Nodes in a list, not counting their descendants
+    (@len) => { 0usize };
+    (@len $name:ident @ $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?) => {
+        1usize + $crate::tree!(@len $($($rest)*)?)
+    };
+    (@len $data:expr $(=> [$($children:tt)*])? $(, $($rest:tt)*)?) => {
+        1usize + $crate::tree!(@len $($($rest)*)?)
+    };
+
+    () => {
+        $crate::Tree::new()
+    };
+    (let $binding:pat = $($root:tt)+) => {
+        let mut _tree = $crate::TreeBuilder::new()
+            .with_node_capacity($crate::tree!(@count $($root)+))
+            .build();
+        $crate::tree!(@root _tree $($root)+);
+        let $binding = _tree;
+    };
+    ($($root:tt)+) => {{
+        let mut _tree = $crate::TreeBuilder::new()
+            .with_node_capacity($crate::tree!(@count $($root)+))
+            .build();
+        $crate::tree!(@root _tree $($root)+);
+        _tree
+    }};
+}
+
+#[cfg(test)]
+mod macros_tests {
+    use super::super::*;
+
+    fn pre_order<T: Clone>(tree: &Tree<T>) -> Vec<T> {
+        match tree.root_node_id() {
+            Some(root_id) => tree
+                .traverse_pre_order(root_id)
+                .unwrap()
+                .map(|node| node.data().clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_shapes() {
+        let empty: Tree<i32> = tree!();
+        assert!(empty.is_empty());
+
+        let leaf = tree!(7);
+        assert_eq!(pre_order(&leaf), vec![7]);
+
+        let tree = tree!(0 => [1 => [3, 4 => []], 2,],);
+        assert_eq!(pre_order(&tree), vec![0, 1, 3, 4, 2]);
+        assert_eq!(tree.capacity(), 5);
+        assert!(tree.validate().is_ok());
+
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.get(root_id).unwrap().children().capacity(), 2);
+
+        let x = 10;
+        let exprs = tree!(x => [x + 1, -x, (x * 2) => [std::cmp::max(x, 3)]]);
+        assert_eq!(pre_order(&exprs), vec![10, 11, -10, 20, 10]);
+    }
+
+    #[test]
+    fn test_named_ids() {
+        tree!(let mut tree = root @ 0 => [a @ 1 => [b @ 2, 3], c @ 4]);
+
+        assert_eq!(tree.root_node_id(), Some(&root));
+        assert_eq!(tree.get(&a).unwrap().data(), &1);
+        assert_eq!(tree.get(&b).unwrap().parent(), Some(&a));
+        assert_eq!(tree.get(&c).unwrap().parent(), Some(&root));
+
+        tree.insert(Node::new(5), InsertBehavior::UnderNode(&c))
+            .unwrap();
+        assert_eq!(pre_order(&tree), vec![0, 1, 2, 3, 4, 5]);
+    }
+}
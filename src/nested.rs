@@ -0,0 +1,457 @@
+//!
+//! A nested serde representation of a `Tree`, for use with `#[serde(with = "id_tree::nested")]`.
+//!
+//! Deriving `Serialize` and `Deserialize` on a `Tree` stores its internals as they are, including
+//! the slots left behind by removed `Node`s.  This module instead writes each `Node` as its data
+//! followed by its children, starting from the root:
+//!
+//! ```text
+//! { "data": 0, "children": [ { "data": 1, "children": [] } ] }
+//! ```
+//!
+//! An empty `Tree` is written as a unit/`null`.  Deserializing builds a fresh, compact `Tree`, so
+//! the `NodeId`s of the original `Tree` are not preserved; use the `with_ids` sub-module if they
+//! need to be.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_json;
+//! extern crate id_tree;
+//!
+//! use id_tree::*;
+//! use id_tree::InsertBehavior::*;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "id_tree::nested")]
+//!     tree: Tree<i32>,
+//! }
+//!
+//! fn main() {
+//!     let mut tree: Tree<i32> = Tree::new();
+//!     let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+//!     tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+//!
+//!     let json = serde_json::to_string(&Config { tree }).unwrap();
+//!     assert_eq!(json, r#"{"tree":{"data":0,"children":[{"data":1,"children":[]}]}}"#);
+//!
+//!     let config: Config = serde_json::from_str(&json).unwrap();
+//!     let root_id = config.tree.root_node_id().unwrap();
+//!     assert_eq!(config.tree.get(root_id).unwrap().children().len(), 1);
+//! }
+//! ```
+//!
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use super::*;
+
+///
+/// Serializes a `Tree` in the nested representation.
+///
+pub fn serialize<T, S>(tree: &Tree<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    tree.root_node_id()
+        .map(|node_id| NestedNode {
+            tree,
+            node_id,
+            with_ids: false,
+        })
+        .serialize(serializer)
+}
+
+///
+/// Deserializes a `Tree` from the nested representation.
+///
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Tree<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let root = Option::<OwnedNode<T>>::deserialize(deserializer)?;
+    build(root, None)
+}
+
+///
+/// The nested representation with each `Node`'s `NodeId` stored alongside it, for use with
+/// `#[serde(with = "id_tree::nested::with_ids")]`.
+///
+/// Each `Node` is written as `{ "id": 3, "data": ..., "children": [...] }`, and the root `Node`
+/// goes next to the `NodeId`s of the `Tree`'s free slots, as
+/// `{ "free_ids": [1, 4], "root": ... }`.  Deserializing puts every `Node` back under its original
+/// `NodeId` and keeps the free slots free, so `NodeId`s held onto elsewhere stay valid.
+///
+/// Orphaned `Node`s aren't written, and their slots are written as free ones.  Every stored
+/// `NodeId` has to be below the number of `Node`s and free slots together, so that a small
+/// document can't make the deserializer allocate an arbitrarily large `Tree`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde_json;
+/// extern crate id_tree;
+///
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+/// use id_tree::RemoveBehavior::*;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "id_tree::nested::with_ids")]
+///     tree: Tree<i32>,
+/// }
+///
+/// fn main() {
+///     let mut tree: Tree<i32> = Tree::new();
+///     let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+///     let removed_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+///     let child_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+///     tree.remove_node(removed_id, DropChildren).unwrap();
+///
+///     let json = serde_json::to_string(&Config { tree }).unwrap();
+///     assert_eq!(
+///         json,
+///         r#"{"tree":{"free_ids":[1],"root":{"id":0,"data":0,"children":[{"id":2,"data":2,"children":[]}]}}}"#
+///     );
+///
+///     let config: Config = serde_json::from_str(&json).unwrap();
+///     assert_eq!(config.tree.get(&child_id).unwrap().data(), &2);
+/// }
+/// ```
+///
+pub mod with_ids {
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+    use super::{build, NestedNode, OwnedTree};
+    use Tree;
+
+    ///
+    /// Serializes a `Tree` in the nested representation, including `NodeId`s and free slots.
+    ///
+    pub fn serialize<T, S>(tree: &Tree<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        let mut reached = vec![false; tree.nodes.len()];
+        if let Some(root_id) = tree.root_node_id() {
+            for node_id in tree.traverse_pre_order_ids(root_id).unwrap() {
+                reached[node_id.index] = true;
+            }
+        }
+        // the free slots, then the ones only orphaned Nodes are in
+        let orphaned =
+            (0..tree.nodes.len()).filter(|&index| tree.nodes[index].is_some() && !reached[index]);
+        let free_ids: Vec<usize> = tree
+            .free_ids
+            .iter()
+            .map(|free_id| free_id.index)
+            .chain(orphaned)
+            .collect();
+
+        let mut state = serializer.serialize_struct("Tree", 2)?;
+        state.serialize_field("free_ids", &free_ids)?;
+        state.serialize_field(
+            "root",
+            &tree.root_node_id().map(|node_id| NestedNode {
+                tree,
+                node_id,
+                with_ids: true,
+            }),
+        )?;
+        state.end()
+    }
+
+    ///
+    /// Deserializes a `Tree` from the nested representation, putting each `Node` back under its
+    /// stored `NodeId`.
+    ///
+    /// Returns an error if a `NodeId` is missing, used more than once (by `Node`s or free slots),
+    /// or not below the number of `Node`s and free slots together.
+    ///
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Tree<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let owned = OwnedTree::<T>::deserialize(deserializer)?;
+        build(owned.root, Some(owned.free_ids))
+    }
+}
+
+struct NestedNode<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    node_id: &'a NodeId,
+    with_ids: bool,
+}
+
+impl<'a, T> Serialize for NestedNode<'a, T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = self.tree.get_unsafe(self.node_id);
+
+        let len = if self.with_ids { 3 } else { 2 };
+        let mut state = serializer.serialize_struct("Node", len)?;
+        if self.with_ids {
+            state.serialize_field("id", &self.node_id.index)?;
+        }
+        state.serialize_field("data", node.data())?;
+        state.serialize_field(
+            "children",
+            &NestedChildren {
+                tree: self.tree,
+                node_id: self.node_id,
+                with_ids: self.with_ids,
+            },
+        )?;
+        state.end()
+    }
+}
+
+struct NestedChildren<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    node_id: &'a NodeId,
+    with_ids: bool,
+}
+
+impl<'a, T> Serialize for NestedChildren<'a, T>
+where
+    T: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let children = self.tree.get_unsafe(self.node_id).children();
+        serializer.collect_seq(children.iter().map(|child_id| NestedNode {
+            tree: self.tree,
+            node_id: child_id,
+            with_ids: self.with_ids,
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Node")]
+struct OwnedNode<T> {
+    #[serde(default)]
+    id: Option<usize>,
+    data: T,
+    children: Vec<OwnedNode<T>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Tree")]
+struct OwnedTree<T> {
+    free_ids: Vec<usize>,
+    root: Option<OwnedNode<T>>,
+}
+
+///
+/// Builds a `Tree` from its nested representation.  With `free_ids`, every `Node` goes back under
+/// its stored `NodeId` and the listed slots are left free.
+///
+fn build<T, E: Error>(
+    root: Option<OwnedNode<T>>,
+    free_ids: Option<Vec<usize>>,
+) -> Result<Tree<T>, E> {
+    let root = match root {
+        Some(root) => root,
+        None => return Ok(Tree::new()),
+    };
+
+    let mut count = 0;
+    let mut stack = vec![&root];
+    while let Some(owned) = stack.pop() {
+        count += 1;
+        stack.extend(owned.children.iter());
+    }
+    // stored NodeIds are only trusted to index into a Tree of this size
+    let slots = count + free_ids.as_ref().map_or(0, Vec::len);
+
+    let mut tree = TreeBuilder::new().with_node_capacity(slots).build();
+    let mut taken = vec![false; slots];
+    let mut take = |index: usize| -> Result<(), E> {
+        match taken.get_mut(index) {
+            None => Err(E::custom(format_args!(
+                "NodeId {} is out of range for a Tree of {} slots",
+                index, slots
+            ))),
+            Some(&mut true) => Err(E::custom(format_args!(
+                "NodeId {} is used more than once",
+                index
+            ))),
+            Some(taken) => {
+                *taken = true;
+                Ok(())
+            }
+        }
+    };
+    if let Some(ref free_ids) = free_ids {
+        tree.nodes.resize_with(slots, || None);
+        for &index in free_ids {
+            take(index)?;
+            tree.free_ids.push(NodeId { index });
+        }
+    }
+
+    // children are pushed in reverse so that they are placed (and added to their parent) in order
+    let mut stack = vec![(root, None)];
+    while let Some((owned, parent)) = stack.pop() {
+        let OwnedNode { id, data, children } = owned;
+
+        let index = if free_ids.is_some() {
+            id.ok_or_else(|| E::missing_field("id"))?
+        } else {
+            tree.nodes.push(None);
+            tree.nodes.len() - 1
+        };
+        take(index)?;
+
+        let node_id = NodeId { index };
+        match parent {
+            Some(ref parent_id) => tree.get_mut_unsafe(parent_id).add_child(node_id.clone()),
+            None => tree.root = Some(node_id.clone()),
+        }
+        tree.nodes[index] = Some(Node {
+            data,
+            parent,
+            children: Vec::with_capacity(children.len()),
+        });
+
+        for child in children.into_iter().rev() {
+            stack.push((child, Some(node_id.clone())));
+        }
+    }
+
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod nested_tests {
+    use serde_json;
+
+    use super::super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Plain {
+        #[serde(with = "::nested")]
+        tree: Tree<i32>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct WithIds {
+        #[serde(with = "::nested::with_ids")]
+        tree: Tree<i32>,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use RemoveBehavior::*;
+
+        // 8 leaves a free slot behind
+        tree!(let mut tree = 0 => [1 => [3], 2 => [removed @ 8]]);
+        tree.remove_node(removed, DropChildren).unwrap();
+
+        let json = serde_json::to_string(&Plain { tree: tree.clone() }).unwrap();
+        assert_eq!(
+            json,
+            "{\"tree\":{\"data\":0,\"children\":[\
+             {\"data\":1,\"children\":[{\"data\":3,\"children\":[]}]},\
+             {\"data\":2,\"children\":[]}]}}"
+        );
+
+        let plain: Plain = serde_json::from_str(&json).unwrap();
+        assert_eq!(plain.tree, tree);
+        assert_eq!(plain.tree.nodes.len(), 4);
+        assert!(plain.tree.free_ids.is_empty());
+    }
+
+    #[test]
+    fn test_empty() {
+        let json = serde_json::to_string(&Plain { tree: Tree::new() }).unwrap();
+        assert_eq!(json, "{\"tree\":null}");
+
+        let plain: Plain = serde_json::from_str(&json).unwrap();
+        assert!(plain.tree.root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_with_ids() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [node_1 @ 1 => [node_3 @ 3], node_2 @ 2 => [removed @ 8]]);
+        tree.remove_node(removed, DropChildren).unwrap();
+
+        let json = serde_json::to_string(&WithIds { tree: tree.clone() }).unwrap();
+        let with_ids: WithIds = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(with_ids.tree, tree);
+        for &(ref node_id, data) in &[(root_id, 0), (node_1, 1), (node_2, 2), (node_3, 3)] {
+            assert_eq!(with_ids.tree.get(node_id).unwrap().data(), &data);
+        }
+
+        let json = "{\"tree\":{\"free_ids\":[],\"root\":{\"id\":0,\"data\":0,\"children\":[\
+                    {\"id\":0,\"data\":1,\"children\":[]}]}}}";
+        assert!(serde_json::from_str::<WithIds>(json).is_err());
+
+        let json = "{\"tree\":{\"free_ids\":[0],\"root\":{\"id\":0,\"data\":0,\"children\":[]}}}";
+        assert!(serde_json::from_str::<WithIds>(json).is_err());
+
+        let json = "{\"tree\":{\"free_ids\":[],\"root\":{\"data\":0,\"children\":[]}}}";
+        assert!(serde_json::from_str::<WithIds>(json).is_err());
+    }
+
+    #[test]
+    fn test_with_ids_hole() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [removed @ 1 => [3], kept @ 2]);
+        tree.remove_node(removed, DropChildren).unwrap();
+
+        let json = serde_json::to_string(&WithIds { tree: tree.clone() }).unwrap();
+        assert_eq!(
+            json,
+            "{\"tree\":{\"free_ids\":[2,1],\"root\":{\"id\":0,\"data\":0,\"children\":[\
+             {\"id\":3,\"data\":2,\"children\":[]}]}}}"
+        );
+
+        let with_ids: WithIds = serde_json::from_str(&json).unwrap();
+        assert_eq!(with_ids.tree, tree);
+        assert_eq!(with_ids.tree.get(&root_id).unwrap().data(), &0);
+        assert_eq!(with_ids.tree.get(&kept).unwrap().data(), &2);
+        assert_eq!(with_ids.tree.nodes.len(), 4);
+        assert_eq!(with_ids.tree.free_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_with_ids_out_of_range() {
+        for id in &["2", "100000000000", "18446744073709551615"] {
+            let json = format!(
+                "{{\"tree\":{{\"free_ids\":[],\"root\":{{\"id\":0,\"data\":0,\"children\":[\
+                 {{\"id\":{},\"data\":1,\"children\":[]}}]}}}}}}",
+                id
+            );
+            let error = serde_json::from_str::<WithIds>(&json).err().unwrap();
+            assert!(error.to_string().contains("out of range"));
+
+            let json = format!(
+                "{{\"tree\":{{\"free_ids\":[{}],\"root\":{{\"id\":0,\"data\":0,\"children\":[]}}}}}}",
+                id
+            );
+            let error = serde_json::from_str::<WithIds>(&json).err().unwrap();
+            assert!(error.to_string().contains("out of range"));
+        }
+
+        let json = "{\"tree\":{\"free_ids\":[],\"root\":{\"id\":1,\"data\":0,\"children\":[\
+                    {\"id\":0,\"data\":1,\"children\":[]}]}}}";
+        let with_ids: WithIds = serde_json::from_str(json).unwrap();
+        assert_eq!(with_ids.tree.root_node_id(), Some(&NodeId { index: 1 }));
+        assert!(with_ids.tree.free_ids.is_empty());
+    }
+}
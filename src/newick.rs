@@ -0,0 +1,538 @@
+use std::fmt::{self, Write};
+
+use super::*;
+
+///
+/// Everything the Newick format can say about a single `Node`.
+///
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NewickNode {
+    /// The `Node`'s name, if it has one.
+    pub label: Option<String>,
+    /// The length of the branch leading to the `Node`, if given.
+    pub length: Option<f64>,
+    /// The contents of the `[...]` comments attached to the `Node`, if any.  Several comments are
+    /// concatenated.
+    pub comment: Option<String>,
+}
+
+///
+/// Converts between a `Tree`'s data and what the Newick format stores for each `Node`.
+///
+/// `NewickNode` implements this itself, so `Tree<NewickNode>` can be used when no other data
+/// type is needed.
+///
+/// ```
+/// use id_tree::*;
+///
+/// struct Taxon {
+///     name: String,
+///     distance: f64,
+/// }
+///
+/// impl NewickData for Taxon {
+///     fn from_newick(node: NewickNode) -> Taxon {
+///         Taxon {
+///             name: node.label.unwrap_or_default(),
+///             distance: node.length.unwrap_or(0.0),
+///         }
+///     }
+///
+///     fn to_newick(&self) -> NewickNode {
+///         NewickNode {
+///             label: Some(self.name.clone()).filter(|name| !name.is_empty()),
+///             length: Some(self.distance),
+///             comment: None,
+///         }
+///     }
+/// }
+///
+/// let tree: Tree<Taxon> = Tree::from_newick("(A:0.1,B:0.2)root:0;").unwrap();
+/// let root_id = tree.root_node_id().unwrap();
+/// assert_eq!(tree.get(root_id).unwrap().data().name, "root");
+/// ```
+///
+pub trait NewickData {
+    ///
+    /// Creates the data for a `Node` read from Newick.
+    ///
+    fn from_newick(node: NewickNode) -> Self;
+
+    ///
+    /// Describes the data of a `Node` to be written as Newick.
+    ///
+    fn to_newick(&self) -> NewickNode;
+}
+
+impl NewickData for NewickNode {
+    fn from_newick(node: NewickNode) -> NewickNode {
+        node
+    }
+
+    fn to_newick(&self) -> NewickNode {
+        self.clone()
+    }
+}
+
+impl<T: NewickData> Tree<T> {
+    ///
+    /// Reads a `Tree` from a Newick string such as `((A:0.1,B:0.2)C,D);`.
+    ///
+    /// Labels may be unquoted (where `_` stands for a space) or quoted with `'` (where `''` stands
+    /// for a `'`).  Branch lengths follow a `:`, and `[...]` comments may appear between any two
+    /// parts of the string; those following a `Node`'s label or length are attached to it.
+    ///
+    /// Returns a `NewickError` if the string isn't a single, complete Newick tree.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<NewickNode> = Tree::from_newick("('Homo sapiens':0.5,Pan_troglodytes)[root];").unwrap();
+    ///
+    /// let root_id = tree.root_node_id().unwrap();
+    /// assert_eq!(tree.get(root_id).unwrap().data().comment.as_deref(), Some("root"));
+    ///
+    /// let labels: Vec<_> = tree
+    ///     .children(root_id)
+    ///     .unwrap()
+    ///     .map(|node| node.data().label.clone().unwrap())
+    ///     .collect();
+    /// assert_eq!(labels, vec!["Homo sapiens", "Pan troglodytes"]);
+    ///
+    /// let error = Tree::<NewickNode>::from_newick("(A,B;").unwrap_err();
+    /// assert_eq!(error, NewickError::UnexpectedChar { position: 4, found: ';' });
+    /// ```
+    ///
+    pub fn from_newick(newick: &str) -> Result<Tree<T>, NewickError> {
+        let (pending, root) = Parser::new(newick).parse()?;
+
+        let mut tree = TreeBuilder::new().with_node_capacity(pending.len()).build();
+        let mut slots: Vec<Option<Pending>> = pending.into_iter().map(Some).collect();
+
+        let mut stack = vec![(root, None)];
+        while let Some((index, parent_id)) = stack.pop() {
+            let (node, children) = slots[index]
+                .take()
+                .expect("Tree::from_newick: Every Node has exactly one parent.");
+            let behavior = match parent_id {
+                Some(ref parent_id) => InsertBehavior::UnderNode(parent_id),
+                None => InsertBehavior::AsRoot,
+            };
+            let node_id = tree
+                .insert(Node::new(T::from_newick(node)), behavior)
+                .expect("Tree::from_newick: The parent was just inserted.");
+            for child in children.into_iter().rev() {
+                stack.push((child, Some(node_id.clone())));
+            }
+        }
+
+        Ok(tree)
+    }
+
+    ///
+    /// Writes the `Tree` in the Newick format, followed by a `;`.  An empty `Tree` writes nothing.
+    ///
+    /// Labels are quoted whenever reading them back unquoted would change them.  Comments can't
+    /// be, as the Newick format has no way to escape a `]` inside one, so a comment containing a
+    /// `]` makes this return an error.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let newick = "((A:0.1,'B c':0.2)D[note],E)F;";
+    /// let tree: Tree<NewickNode> = Tree::from_newick(newick).unwrap();
+    ///
+    /// let mut s = String::new();
+    /// tree.write_newick(&mut s).unwrap();
+    /// assert_eq!(s, newick);
+    /// ```
+    ///
+    pub fn write_newick<W: Write>(&self, w: &mut W) -> fmt::Result {
+        let root_id = match self.root_node_id() {
+            Some(root_id) => root_id,
+            None => return Ok(()),
+        };
+
+        let mut stack = vec![Step::Enter(root_id)];
+        while let Some(step) = stack.pop() {
+            let node_id = match step {
+                Step::Enter(node_id) => node_id,
+                Step::Separate => {
+                    w.write_char(',')?;
+                    continue;
+                }
+                Step::Close(node_id) => {
+                    w.write_char(')')?;
+                    write_tail(w, &self.get_unsafe(node_id).data().to_newick())?;
+                    continue;
+                }
+            };
+            let node = self.get_unsafe(node_id);
+
+            if node.children().is_empty() {
+                write_tail(w, &node.data().to_newick())?;
+                continue;
+            }
+
+            w.write_char('(')?;
+            stack.push(Step::Close(node_id));
+            for (i, child_id) in node.children().iter().enumerate().rev() {
+                stack.push(Step::Enter(child_id));
+                if i > 0 {
+                    stack.push(Step::Separate);
+                }
+            }
+        }
+
+        w.write_char(';')
+    }
+}
+
+enum Step<'a> {
+    Enter(&'a NodeId),
+    Separate,
+    Close(&'a NodeId),
+}
+
+fn write_tail<W: Write>(w: &mut W, node: &NewickNode) -> fmt::Result {
+    if let Some(ref label) = node.label {
+        write_label(w, label)?;
+    }
+    if let Some(length) = node.length {
+        write!(w, ":{}", length)?;
+    }
+    if let Some(ref comment) = node.comment {
+        // a ']' would end the comment early
+        if comment.contains(']') {
+            return Err(fmt::Error);
+        }
+        write!(w, "[{}]", comment)?;
+    }
+    Ok(())
+}
+
+fn write_label<W: Write>(w: &mut W, label: &str) -> fmt::Result {
+    let needs_quotes = label.is_empty() || label.chars().any(|c| is_special(c) || c == '_');
+    if !needs_quotes {
+        return w.write_str(label);
+    }
+
+    w.write_char('\'')?;
+    for c in label.chars() {
+        if c == '\'' {
+            w.write_str("''")?;
+        } else {
+            w.write_char(c)?;
+        }
+    }
+    w.write_char('\'')
+}
+
+fn is_special(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '\'' | ':' | ';' | ',')
+}
+
+/// A parsed `Node` along with the indices of its children.
+type Pending = (NewickNode, Vec<usize>);
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    pending: Vec<Pending>,
+    open: Vec<usize>,
+    root: Option<usize>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Parser<'a> {
+        Parser {
+            input,
+            position: 0,
+            pending: Vec::new(),
+            open: Vec::new(),
+            root: None,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn unexpected(&self) -> NewickError {
+        match self.peek() {
+            Some(found) => NewickError::UnexpectedChar {
+                position: self.position,
+                found,
+            },
+            None => NewickError::UnexpectedEnd {
+                position: self.position,
+            },
+        }
+    }
+
+    ///
+    /// Parses the whole input, returning every `Node` (with the indices of its children) and the
+    /// index of the root.
+    ///
+    fn parse(mut self) -> Result<(Vec<Pending>, usize), NewickError> {
+        // the Node whose label, length and comments are currently being read, if any
+        let mut current: Option<usize> = None;
+
+        loop {
+            self.skip_space(current)?;
+
+            match self.peek() {
+                Some('(') if current.is_none() => {
+                    let index = self.new_node()?;
+                    self.open.push(index);
+                    self.position += 1;
+                }
+                Some(',') if !self.open.is_empty() => {
+                    if current.is_none() {
+                        self.new_node()?;
+                    }
+                    current = None;
+                    self.position += 1;
+                }
+                Some(')') if !self.open.is_empty() => {
+                    if current.is_none() {
+                        self.new_node()?;
+                    }
+                    current = self.open.pop();
+                    self.position += 1;
+                }
+                Some(';') if self.open.is_empty() => {
+                    if current.is_none() {
+                        self.new_node()?;
+                    }
+                    self.position += 1;
+                    self.skip_space(None)?;
+                    if self.peek().is_some() {
+                        return Err(self.unexpected());
+                    }
+                    break;
+                }
+                Some(':') => {
+                    let index = match current {
+                        Some(index) => index,
+                        None => self.new_node()?,
+                    };
+                    if self.pending[index].0.length.is_some() {
+                        return Err(self.unexpected());
+                    }
+                    self.position += 1;
+                    let length = self.read_length()?;
+                    self.pending[index].0.length = Some(length);
+                    current = Some(index);
+                }
+                Some(c) if c == '\'' || !is_special(c) => {
+                    let index = match current {
+                        Some(index) => index,
+                        None => self.new_node()?,
+                    };
+                    let node = &self.pending[index].0;
+                    if node.label.is_some() || node.length.is_some() {
+                        return Err(self.unexpected());
+                    }
+                    let label = self.read_label()?;
+                    self.pending[index].0.label = Some(label);
+                    current = Some(index);
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+
+        let root = self
+            .root
+            .expect("Parser::parse: A root is created before the ';'.");
+        Ok((self.pending, root))
+    }
+
+    fn new_node(&mut self) -> Result<usize, NewickError> {
+        let index = self.pending.len();
+        match self.open.last() {
+            Some(&parent) => self.pending[parent].1.push(index),
+            None if self.root.is_some() => return Err(self.unexpected()),
+            None => self.root = Some(index),
+        }
+        self.pending.push((NewickNode::default(), Vec::new()));
+        Ok(index)
+    }
+
+    ///
+    /// Skips whitespace and comments, attaching the comments to the `current` `Node`, if any.
+    ///
+    fn skip_space(&mut self, current: Option<usize>) -> Result<(), NewickError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => self.position += c.len_utf8(),
+                Some('[') => {
+                    let start = self.position;
+                    let end = match self.input[start..].find(']') {
+                        Some(end) => start + end,
+                        None => return Err(NewickError::Unterminated { position: start }),
+                    };
+                    if let Some(index) = current {
+                        let comment = &self.input[start + 1..end];
+                        self.pending[index]
+                            .0
+                            .comment
+                            .get_or_insert_with(String::new)
+                            .push_str(comment);
+                    }
+                    self.position = end + 1;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn read_label(&mut self) -> Result<String, NewickError> {
+        let start = self.position;
+
+        if self.peek() != Some('\'') {
+            let rest = &self.input[start..];
+            let len = rest.find(is_special).unwrap_or(rest.len());
+            self.position += len;
+            return Ok(rest[..len].replace('_', " "));
+        }
+
+        let mut label = String::new();
+        let mut chars = self.input[start + 1..].char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '\'' {
+                label.push(c);
+                continue;
+            }
+            if let Some(&(_, '\'')) = chars.peek() {
+                chars.next();
+                label.push('\'');
+                continue;
+            }
+            self.position = start + 1 + i + 1;
+            return Ok(label);
+        }
+
+        Err(NewickError::Unterminated { position: start })
+    }
+
+    fn read_length(&mut self) -> Result<f64, NewickError> {
+        self.skip_space(None)?;
+        let start = self.position;
+        let rest = &self.input[start..];
+        let len = rest.find(is_special).unwrap_or(rest.len());
+        self.position += len;
+
+        rest[..len]
+            .parse()
+            .map_err(|_| NewickError::InvalidLength { position: start })
+    }
+}
+
+#[cfg(test)]
+mod newick_tests {
+    use super::super::*;
+
+    fn labels(tree: &Tree<NewickNode>) -> Vec<String> {
+        tree.traverse_pre_order(tree.root_node_id().unwrap())
+            .unwrap()
+            .map(|node| node.data().label.clone().unwrap_or_default())
+            .collect()
+    }
+
+    fn newick(tree: &Tree<NewickNode>) -> String {
+        let mut s = String::new();
+        tree.write_newick(&mut s).unwrap();
+        s
+    }
+
+    #[test]
+    fn test_structure() {
+        let tree: Tree<NewickNode> = Tree::from_newick("((A,B)C,(D)E,)F;").unwrap();
+        assert_eq!(labels(&tree), vec!["F", "C", "A", "B", "E", "D", ""]);
+
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.get(root_id).unwrap().children().len(), 3);
+
+        let tree: Tree<NewickNode> = Tree::from_newick(";").unwrap();
+        assert_eq!(labels(&tree), vec![""]);
+        assert_eq!(newick(&tree), ";");
+    }
+
+    #[test]
+    fn test_labels_lengths_and_comments() {
+        let tree: Tree<NewickNode> =
+            Tree::from_newick(" ( 'it''s' : 1.5e-1 [a] [b] , x_y:-2 )\n[&&NHX:S=1] ; ").unwrap();
+        let root_id = tree.root_node_id().unwrap();
+        let children: Vec<&NewickNode> = tree.children(root_id).unwrap().map(Node::data).collect();
+
+        assert_eq!(
+            children[0],
+            &NewickNode {
+                label: Some("it's".to_string()),
+                length: Some(0.15),
+                comment: Some("ab".to_string()),
+            }
+        );
+        assert_eq!(children[1].label.as_deref(), Some("x y"));
+        assert_eq!(children[1].length, Some(-2.0));
+        assert_eq!(
+            tree.get(root_id).unwrap().data().comment.as_deref(),
+            Some("&&NHX:S=1")
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "(('it''s':0.15,'x_y',''),(:1,(a:2)b)[c]);";
+        let tree: Tree<NewickNode> = Tree::from_newick(source).unwrap();
+        assert_eq!(newick(&tree), source);
+
+        let reread: Tree<NewickNode> = Tree::from_newick(&newick(&tree)).unwrap();
+        assert_eq!(reread, tree);
+
+        assert_eq!(newick(&Tree::new()), "");
+
+        let mut tree = tree;
+        let root_id = tree.root_node_id().unwrap().clone();
+        tree.get_mut(&root_id).unwrap().data_mut().comment = Some("a]b".to_string());
+        assert!(tree.write_newick(&mut String::new()).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        fn parse(s: &str) -> NewickError {
+            Tree::<NewickNode>::from_newick(s).unwrap_err()
+        }
+
+        assert_eq!(parse(""), NewickError::UnexpectedEnd { position: 0 });
+        assert_eq!(parse("(A,B)"), NewickError::UnexpectedEnd { position: 5 });
+        assert_eq!(
+            parse("A;B;"),
+            NewickError::UnexpectedChar {
+                position: 2,
+                found: 'B',
+            }
+        );
+        assert_eq!(
+            parse("(A)B C;"),
+            NewickError::UnexpectedChar {
+                position: 5,
+                found: 'C',
+            }
+        );
+        assert_eq!(
+            parse("A,B;"),
+            NewickError::UnexpectedChar {
+                position: 1,
+                found: ',',
+            }
+        );
+        assert_eq!(
+            parse("(A:x,B);"),
+            NewickError::InvalidLength { position: 3 }
+        );
+        assert_eq!(parse("('A,B);"), NewickError::Unterminated { position: 1 });
+        assert_eq!(parse("(A[,B);"), NewickError::Unterminated { position: 2 });
+    }
+}
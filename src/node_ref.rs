@@ -0,0 +1,295 @@
+use std::fmt;
+
+use super::*;
+
+///
+/// A `Node` together with the `Tree` it is in, so that its relatives can be reached without
+/// passing `NodeId`s back to the `Tree`.
+///
+/// Returned by `Tree::node_ref` and `Tree::root_ref`, and by the navigation methods of `NodeRef`
+/// itself.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<i32> = Tree::new();
+/// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+/// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+/// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+///
+/// let root = tree.root_ref().unwrap();
+/// let grandchild = root.children().flat_map(|child| child.children()).next().unwrap();
+/// assert_eq!(grandchild.data(), &2);
+/// assert_eq!(grandchild.parent().unwrap().id(), &child_id);
+/// ```
+///
+pub struct NodeRef<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    node_id: NodeId,
+}
+
+impl<'a, T> NodeRef<'a, T> {
+    fn new(tree: &'a Tree<T>, node_id: NodeId) -> NodeRef<'a, T> {
+        NodeRef { tree, node_id }
+    }
+
+    ///
+    /// Returns the `NodeId` of this `Node`.
+    ///
+    pub fn id(&self) -> &NodeId {
+        &self.node_id
+    }
+
+    ///
+    /// Returns the `Tree` this `Node` is in.
+    ///
+    pub fn tree(&self) -> &'a Tree<T> {
+        self.tree
+    }
+
+    ///
+    /// Returns this `Node`.
+    ///
+    pub fn node(&self) -> &'a Node<T> {
+        self.tree.get_unsafe(&self.node_id)
+    }
+
+    ///
+    /// Returns the data of this `Node`.
+    ///
+    pub fn data(&self) -> &'a T {
+        self.node().data()
+    }
+
+    ///
+    /// Returns the parent of this `Node`, or `None` if it has none.
+    ///
+    pub fn parent(&self) -> Option<NodeRef<'a, T>> {
+        self.node()
+            .parent()
+            .map(|parent_id| NodeRef::new(self.tree, parent_id.clone()))
+    }
+
+    ///
+    /// Returns an `Iterator` over the children of this `Node`, in order.
+    ///
+    pub fn children(&self) -> NodeRefChildren<'a, T> {
+        NodeRefChildren {
+            tree: self.tree,
+            ids: ChildrenIds::new(self.tree, self.node_id.clone()),
+        }
+    }
+
+    ///
+    /// Returns an `Iterator` over the ancestors of this `Node`, starting with its parent.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let grandchild_id = tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// let grandchild = tree.node_ref(&grandchild_id).unwrap();
+    /// let data: Vec<i32> = grandchild.ancestors().map(|node| *node.data()).collect();
+    /// assert_eq!(data, vec![1, 0]);
+    /// ```
+    ///
+    pub fn ancestors(&self) -> NodeRefAncestors<'a, T> {
+        NodeRefAncestors {
+            tree: self.tree,
+            ids: AncestorIds::new(self.tree, self.node_id.clone()),
+        }
+    }
+
+    ///
+    /// Returns an `Iterator` over the descendants of this `Node` in pre-order, not including the
+    /// `Node` itself.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    /// tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+    ///
+    /// let root = tree.root_ref().unwrap();
+    /// let data: Vec<i32> = root.descendants().map(|node| *node.data()).collect();
+    /// assert_eq!(data, vec![1, 2, 3]);
+    /// ```
+    ///
+    pub fn descendants(&self) -> NodeRefDescendants<'a, T> {
+        let mut ids = PreOrderTraversalIds::new(self.tree, self.node_id.clone());
+        ids.next();
+        NodeRefDescendants {
+            tree: self.tree,
+            ids,
+        }
+    }
+}
+
+impl<'a, T> Clone for NodeRef<'a, T> {
+    fn clone(&self) -> Self {
+        NodeRef::new(self.tree, self.node_id.clone())
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for NodeRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("node_id", &self.node_id)
+            .field("data", self.data())
+            .finish()
+    }
+}
+
+///
+/// An Iterator over the children of a `NodeRef`.
+///
+pub struct NodeRefChildren<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    ids: ChildrenIds<'a>,
+}
+
+impl<'a, T> Iterator for NodeRefChildren<'a, T> {
+    type Item = NodeRef<'a, T>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, T>> {
+        let tree = self.tree;
+        self.ids
+            .next()
+            .map(|node_id| NodeRef::new(tree, node_id.clone()))
+    }
+}
+
+impl<'a, T> Clone for NodeRefChildren<'a, T> {
+    fn clone(&self) -> Self {
+        NodeRefChildren {
+            tree: self.tree,
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+///
+/// An Iterator over the ancestors of a `NodeRef`.
+///
+pub struct NodeRefAncestors<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    ids: AncestorIds<'a, T>,
+}
+
+impl<'a, T> Iterator for NodeRefAncestors<'a, T> {
+    type Item = NodeRef<'a, T>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, T>> {
+        let tree = self.tree;
+        self.ids
+            .next()
+            .map(|node_id| NodeRef::new(tree, node_id.clone()))
+    }
+}
+
+impl<'a, T> Clone for NodeRefAncestors<'a, T> {
+    fn clone(&self) -> Self {
+        NodeRefAncestors {
+            tree: self.tree,
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+///
+/// An Iterator over the descendants of a `NodeRef`, in pre-order.
+///
+pub struct NodeRefDescendants<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    ids: PreOrderTraversalIds<'a, T>,
+}
+
+impl<'a, T> Iterator for NodeRefDescendants<'a, T> {
+    type Item = NodeRef<'a, T>;
+
+    fn next(&mut self) -> Option<NodeRef<'a, T>> {
+        let tree = self.tree;
+        self.ids.next().map(|node_id| NodeRef::new(tree, node_id))
+    }
+}
+
+impl<'a, T> Clone for NodeRefDescendants<'a, T> {
+    fn clone(&self) -> Self {
+        NodeRefDescendants {
+            tree: self.tree,
+            ids: self.ids.clone(),
+        }
+    }
+}
+
+impl<T> Tree<T> {
+    ///
+    /// Returns a `NodeRef` for the `Node` with the given `NodeId`.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is not valid for this `Tree`.
+    ///
+    pub fn node_ref(&self, node_id: &NodeId) -> Result<NodeRef<'_, T>, NodeIdError> {
+        self.get(node_id)?;
+        Ok(NodeRef::new(self, node_id.clone()))
+    }
+
+    ///
+    /// Returns a `NodeRef` for the root `Node`, or `None` if the `Tree` is empty.
+    ///
+    pub fn root_ref(&self) -> Option<NodeRef<'_, T>> {
+        self.root_node_id()
+            .map(|root_id| NodeRef::new(self, root_id.clone()))
+    }
+}
+
+#[cfg(test)]
+mod node_ref_tests {
+    use super::super::*;
+
+    fn data<'a, I: Iterator<Item = NodeRef<'a, i32>>>(iter: I) -> Vec<i32> {
+        iter.map(|node| *node.data()).collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        tree!(let tree = root_id @ 0 => [node_1 @ 1 => [node_2 @ 2, node_3 @ 3], node_4 @ 4]);
+        let root = tree.root_ref().unwrap();
+        assert_eq!(root.id(), &root_id);
+        assert!(root.parent().is_none());
+        assert_eq!(data(root.children()), vec![1, 4]);
+        assert_eq!(data(root.descendants()), vec![1, 2, 3, 4]);
+        assert_eq!(data(root.ancestors()), Vec::<i32>::new());
+
+        let node_3 = tree.node_ref(&node_3).unwrap();
+        assert_eq!(node_3.node().children().len(), 0);
+        assert_eq!(data(node_3.ancestors()), vec![1, 0]);
+        assert_eq!(data(node_3.descendants()), Vec::<i32>::new());
+        assert_eq!(node_3.parent().unwrap().id(), &node_1);
+
+        let leaves: Vec<NodeId> = root
+            .descendants()
+            .filter(|node| node.children().next().is_none())
+            .map(|node| node.id().clone())
+            .collect();
+        assert_eq!(leaves, vec![node_2, node_3.id().clone(), node_4]);
+    }
+
+    #[test]
+    fn test_invalid_id() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = 0 => [node_1 @ 1 => [node_2 @ 2]]);
+        tree.remove_node(node_1, DropChildren).unwrap();
+        assert!(tree.node_ref(&node_2).is_err());
+        assert!(Tree::<i32>::new().root_ref().is_none());
+    }
+}
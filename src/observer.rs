@@ -0,0 +1,324 @@
+use std::fmt;
+
+use super::*;
+
+///
+/// Describes a change made to a `Tree`.  These are handed to every observer registered with
+/// `Tree::add_observer`, after the change has been made.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEvent {
+    ///
+    /// A `Node` was inserted.  `parent` is `None` when it was inserted as the root, and `pos` is
+    /// its position amongst its siblings.
+    ///
+    Inserted {
+        id: NodeId,
+        parent: Option<NodeId>,
+        pos: usize,
+    },
+    ///
+    /// A `Node` was removed.  With `RemoveBehavior::DropChildren` this single event stands for the
+    /// whole sub-tree.
+    ///
+    Removed {
+        id: NodeId,
+        behavior: RemoveBehavior,
+    },
+    ///
+    /// A `Node` was moved.  Either parent is `None` when the `Node` was, or has become, the root.
+    ///
+    /// A move or an insertion that gives another `Node` a new parent as well (such as the old root
+    /// when a new one takes its place) reports that `Node` with its own `Moved` event.
+    ///
+    Moved {
+        id: NodeId,
+        old_parent: Option<NodeId>,
+        new_parent: Option<NodeId>,
+    },
+    ///
+    /// Two `Node`s were swapped.
+    ///
+    Swapped {
+        first_id: NodeId,
+        second_id: NodeId,
+        behavior: SwapBehavior,
+    },
+    ///
+    /// The children of a `Node` were sorted or otherwise put in a different order.
+    ///
+    ChildrenReordered { parent: NodeId },
+    ///
+    /// The data of a `Node` was replaced through `Tree::replace_data`.
+    ///
+    DataReplaced { id: NodeId },
+    ///
+    /// A rolled back `Transaction`, or an undo or redo on a `HistoryTree`, restored an earlier
+    /// state of the `Tree` without going through its mutation methods.  Anything mirroring the
+    /// `Tree` should resynchronize from scratch.
+    ///
+    Restored,
+    ///
+    /// `Tree::compact` or `Tree::compact_pre_order` moved `Node`s to new `NodeId`s.  The
+    /// `NodeIdRemap` they return says where each one went.
+    ///
+    Compacted,
+}
+
+///
+/// Identifies an observer registered with `Tree::add_observer`, so that it can be removed again.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId {
+    index: usize,
+}
+
+type Observer = Box<dyn FnMut(&TreeEvent) + Send + Sync>;
+
+///
+/// The observers registered with a `Tree`.
+///
+/// Observers are tied to a particular `Tree` value, so clones of a `Tree` (and deserialized
+/// `Tree`s) start out with none.
+///
+#[derive(Default)]
+pub(crate) struct Observers {
+    next_index: usize,
+    observers: Vec<(ObserverId, Observer)>,
+}
+
+impl Observers {
+    pub(crate) fn add(&mut self, observer: Observer) -> ObserverId {
+        let id = ObserverId {
+            index: self.next_index,
+        };
+        self.next_index += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: ObserverId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|&(observer_id, _)| observer_id != id);
+        self.observers.len() != len
+    }
+
+    pub(crate) fn notify<F>(&mut self, event: F)
+    where
+        F: FnOnce() -> TreeEvent,
+    {
+        // building the event clones NodeIds, so don't bother unless someone is listening
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let event = event();
+        for &mut (_, ref mut observer) in self.observers.iter_mut() {
+            observer(&event);
+        }
+    }
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Observers {
+        Observers::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.observers.len())
+    }
+}
+
+#[cfg(test)]
+mod observer_tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::super::*;
+
+    fn record(tree: &mut Tree<i32>) -> (ObserverId, Arc<Mutex<Vec<TreeEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let id = tree.add_observer(move |event| sink.lock().unwrap().push(event.clone()));
+        (id, events)
+    }
+
+    #[test]
+    fn test_events() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+        use SwapBehavior::*;
+
+        let mut tree = Tree::new();
+        let (_, events) = record(&mut tree);
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        tree.move_node(&node_2, ToParent(&node_1)).unwrap();
+        tree.swap_nodes(&node_1, &node_2, TakeChildren).unwrap();
+        tree.sort_children_by_data(&root_id).unwrap();
+        tree.replace_data(&node_1, 10).unwrap();
+        tree.remove_node(node_2.clone(), DropChildren).unwrap();
+
+        // failed changes aren't reported
+        assert!(tree.insert(Node::new(3), UnderNode(&node_1)).is_err());
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                TreeEvent::Inserted {
+                    id: root_id.clone(),
+                    parent: None,
+                    pos: 0,
+                },
+                TreeEvent::Inserted {
+                    id: node_1.clone(),
+                    parent: Some(root_id.clone()),
+                    pos: 0,
+                },
+                TreeEvent::Inserted {
+                    id: node_2.clone(),
+                    parent: Some(root_id.clone()),
+                    pos: 1,
+                },
+                TreeEvent::Moved {
+                    id: node_2.clone(),
+                    old_parent: Some(root_id.clone()),
+                    new_parent: Some(node_1.clone()),
+                },
+                TreeEvent::Swapped {
+                    first_id: node_1.clone(),
+                    second_id: node_2.clone(),
+                    behavior: TakeChildren,
+                },
+                TreeEvent::ChildrenReordered {
+                    parent: root_id.clone(),
+                },
+                TreeEvent::DataReplaced { id: node_1.clone() },
+                TreeEvent::Removed {
+                    id: node_2,
+                    behavior: DropChildren,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_displaced_nodes() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_a = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_b = tree.insert(Node::new(2), UnderNode(&node_a)).unwrap();
+        let node_c = tree.insert(Node::new(3), UnderNode(&node_b)).unwrap();
+        let (_, events) = record(&mut tree);
+
+        // b is re-attached to the root on the way
+        tree.move_node(&node_a, ToParent(&node_c)).unwrap();
+        // the old root ends up beneath c
+        tree.move_node(&node_c, ToRoot).unwrap();
+        // and c beneath the new root
+        let new_root_id = tree.insert(Node::new(4), AsRoot).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                TreeEvent::Moved {
+                    id: node_b.clone(),
+                    old_parent: Some(node_a.clone()),
+                    new_parent: Some(root_id.clone()),
+                },
+                TreeEvent::Moved {
+                    id: node_a.clone(),
+                    old_parent: Some(root_id.clone()),
+                    new_parent: Some(node_c.clone()),
+                },
+                TreeEvent::Moved {
+                    id: node_c.clone(),
+                    old_parent: Some(node_b.clone()),
+                    new_parent: None,
+                },
+                TreeEvent::Moved {
+                    id: root_id.clone(),
+                    old_parent: None,
+                    new_parent: Some(node_c.clone()),
+                },
+                TreeEvent::Inserted {
+                    id: new_root_id.clone(),
+                    parent: None,
+                    pos: 0,
+                },
+                TreeEvent::Moved {
+                    id: node_c.clone(),
+                    old_parent: None,
+                    new_parent: Some(new_root_id.clone()),
+                },
+            ]
+        );
+
+        // the events are enough to keep track of every Node's parent
+        let mut parents = vec![None, Some(root_id.clone()), Some(node_a), Some(node_b)];
+        for event in events.lock().unwrap().iter() {
+            match *event {
+                TreeEvent::Moved {
+                    ref id,
+                    ref new_parent,
+                    ..
+                } => parents[id.index] = new_parent.clone(),
+                TreeEvent::Inserted { ref parent, .. } => parents.push(parent.clone()),
+                _ => unreachable!(),
+            }
+        }
+        for (index, parent) in parents.iter().enumerate() {
+            assert_eq!(
+                tree.get(&NodeId { index }).unwrap().parent(),
+                parent.as_ref()
+            );
+        }
+    }
+
+    #[test]
+    fn test_remove_observer() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let (first, first_events) = record(&mut tree);
+        let (_, second_events) = record(&mut tree);
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        assert!(tree.remove_observer(first));
+        assert!(!tree.remove_observer(first));
+        tree.make_first_sibling(&root_id).unwrap();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        assert_eq!(first_events.lock().unwrap().len(), 1);
+        assert_eq!(second_events.lock().unwrap().len(), 2);
+
+        // clones don't take the observers along
+        let mut clone = tree.clone();
+        clone.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+        assert_eq!(second_events.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_restored() {
+        use InsertBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let (_, events) = record(&mut tree);
+
+        let result: Result<(), ()> = tree.transaction(|tx| {
+            tx.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            Err(())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(events.lock().unwrap().last(), Some(&TreeEvent::Restored));
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::*;
+
+impl<T> Tree<T> {
+    ///
+    /// Builds a `Tree` from flat records that each name their own key and the key of their parent,
+    /// such as rows read from a table.  Each record becomes the data of a `Node`.
+    ///
+    /// `key_fn` returns the key of a record and `parent_fn` the key of its parent, or `None` for
+    /// the root.  The records may come in any order; children end up in the order their records
+    /// came in.
+    ///
+    /// Returns the `Tree` along with the `NodeId` each key ended up with, or a
+    /// `ParentLinksError` if two records share a key, if more than one record has no parent, if a
+    /// record names a parent that doesn't exist, or if a record is its own ancestor.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let rows = vec![(3, Some(1), "c"), (1, None, "a"), (2, Some(1), "b")];
+    /// let (tree, ids) = Tree::from_parent_links(rows, |row| row.0, |row| row.1).unwrap();
+    ///
+    /// assert_eq!(tree.root_node_id(), Some(&ids[&1]));
+    /// let children: Vec<&str> = tree.children(&ids[&1]).unwrap().map(|node| node.data().2).collect();
+    /// assert_eq!(children, vec!["c", "b"]);
+    ///
+    /// let rows = vec![(1, None, "a"), (2, Some(4), "b")];
+    /// let error = Tree::from_parent_links(rows, |row| row.0, |row| row.1).unwrap_err();
+    /// assert_eq!(error, ParentLinksError::MissingParent { key: 2, parent: 4 });
+    /// ```
+    ///
+    #[allow(clippy::type_complexity)]
+    pub fn from_parent_links<I, K, F, P>(
+        records: I,
+        mut key_fn: F,
+        mut parent_fn: P,
+    ) -> Result<(Tree<T>, HashMap<K, NodeId>), ParentLinksError<K>>
+    where
+        I: IntoIterator<Item = T>,
+        K: Hash + Eq + Clone,
+        F: FnMut(&T) -> K,
+        P: FnMut(&T) -> Option<K>,
+    {
+        let records = records.into_iter();
+        let (capacity, _) = records.size_hint();
+        let mut tree = TreeBuilder::new().with_node_capacity(capacity).build();
+        let mut ids: HashMap<K, NodeId> = HashMap::with_capacity(capacity);
+        // the key and parent key of the record at each index
+        let mut keys = Vec::with_capacity(capacity);
+        let mut parents = Vec::with_capacity(capacity);
+
+        for data in records {
+            let key = key_fn(&data);
+            let node_id = NodeId {
+                index: tree.nodes.len(),
+            };
+            if ids.insert(key.clone(), node_id).is_some() {
+                return Err(ParentLinksError::DuplicateKey(key));
+            }
+            keys.push(key);
+            parents.push(parent_fn(&data));
+            tree.nodes.push(Some(Node::new(data)));
+        }
+
+        for (index, parent) in parents.into_iter().enumerate() {
+            let node_id = NodeId { index };
+            let parent_key = match parent {
+                Some(parent_key) => parent_key,
+                None => {
+                    if let Some(ref root_id) = tree.root {
+                        return Err(ParentLinksError::MultipleRoots {
+                            first: keys[root_id.index].clone(),
+                            second: keys[index].clone(),
+                        });
+                    }
+                    tree.root = Some(node_id);
+                    continue;
+                }
+            };
+
+            let parent_id = match ids.get(&parent_key) {
+                Some(parent_id) => parent_id.clone(),
+                None => {
+                    return Err(ParentLinksError::MissingParent {
+                        key: keys[index].clone(),
+                        parent: parent_key,
+                    })
+                }
+            };
+            tree.get_mut_unsafe(&parent_id).add_child(node_id.clone());
+            tree.get_mut_unsafe(&node_id).set_parent(Some(parent_id));
+        }
+
+        // every record not below the root must be on, or hang off, a cycle
+        let mut reached = vec![false; keys.len()];
+        if let Some(root_id) = tree.root.clone() {
+            for node_id in tree.traverse_pre_order_ids(&root_id).unwrap() {
+                reached[node_id.index] = true;
+            }
+        }
+        if let Some(mut index) = reached.iter().position(|&reached| !reached) {
+            let mut seen = vec![false; keys.len()];
+            while !seen[index] {
+                seen[index] = true;
+                index = tree
+                    .get_unsafe(&NodeId { index })
+                    .parent()
+                    .expect("Tree::from_parent_links: Only the root has no parent.")
+                    .index;
+            }
+            return Err(ParentLinksError::Cycle(keys[index].clone()));
+        }
+
+        Ok((tree, ids))
+    }
+
+    ///
+    /// Returns an `Iterator` over the `Tree` as flat records, the inverse of
+    /// `Tree::from_parent_links`.
+    ///
+    /// Each record is a `Node`'s `NodeId`, the `NodeId` of its parent (`None` for the root), its
+    /// position among its siblings and its data.  Records come in pre-order, so every parent comes
+    /// before its children and siblings come in order.  `Node`s that can't be reached from the
+    /// root are left out.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("a"), AsRoot).unwrap();
+    /// let b_id = tree.insert(Node::new("b"), UnderNode(&root_id)).unwrap();
+    /// let c_id = tree.insert(Node::new("c"), UnderNode(&root_id)).unwrap();
+    ///
+    /// let links: Vec<_> = tree.to_parent_links().collect();
+    /// assert_eq!(
+    ///     links,
+    ///     vec![
+    ///         (root_id.clone(), None, 0, &"a"),
+    ///         (b_id, Some(root_id.clone()), 0, &"b"),
+    ///         (c_id, Some(root_id.clone()), 1, &"c"),
+    ///     ]
+    /// );
+    ///
+    /// let (rebuilt, _) = Tree::from_parent_links(
+    ///     links.into_iter().map(|(id, parent, _, data)| (id, parent, *data)),
+    ///     |row| row.0.clone(),
+    ///     |row| row.1.clone(),
+    /// )
+    /// .unwrap();
+    /// let data: Vec<&str> = rebuilt.to_parent_links().map(|(_, _, _, row)| row.2).collect();
+    /// assert_eq!(data, vec!["a", "b", "c"]);
+    /// ```
+    ///
+    pub fn to_parent_links(&self) -> ParentLinks<'_, T> {
+        ParentLinks {
+            tree: self,
+            stack: self
+                .root
+                .iter()
+                .map(|root_id| (root_id.clone(), 0))
+                .collect(),
+        }
+    }
+}
+
+///
+/// An Iterator over a `Tree` as flat parent-link records, in pre-order.
+///
+/// Returned by `Tree::to_parent_links`.
+///
+pub struct ParentLinks<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    // the Nodes still to visit, with their positions among their siblings
+    stack: Vec<(NodeId, usize)>,
+}
+
+impl<'a, T> Iterator for ParentLinks<'a, T> {
+    type Item = (NodeId, Option<NodeId>, usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node_id, position) = self.stack.pop()?;
+        let node = self.tree.get_unsafe(&node_id);
+        self.stack.extend(
+            node.children()
+                .iter()
+                .cloned()
+                .enumerate()
+                .rev()
+                .map(|(position, child_id)| (child_id, position)),
+        );
+        Some((node_id, node.parent().cloned(), position, node.data()))
+    }
+}
+
+impl<'a, T> Clone for ParentLinks<'a, T> {
+    fn clone(&self) -> Self {
+        ParentLinks {
+            tree: self.tree,
+            stack: self.stack.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod parent_links_tests {
+    use super::super::*;
+    use std::collections::HashMap;
+
+    type Row = (&'static str, Option<&'static str>);
+    type Built = (Tree<Row>, HashMap<&'static str, NodeId>);
+
+    fn build(rows: Vec<Row>) -> Result<Built, ParentLinksError<&'static str>> {
+        Tree::from_parent_links(rows, |row| row.0, |row| row.1)
+    }
+
+    #[test]
+    fn test_from_parent_links() {
+        let rows = vec![
+            ("d", Some("b")),
+            ("b", Some("a")),
+            ("c", Some("a")),
+            ("a", None),
+            ("e", Some("b")),
+        ];
+        let (tree, ids) = build(rows).unwrap();
+        assert!(tree.validate().is_ok());
+        assert_eq!(ids.len(), 5);
+        assert_eq!(tree.root_node_id(), Some(&ids["a"]));
+
+        let keys: Vec<&str> = tree
+            .traverse_pre_order(&ids["a"])
+            .unwrap()
+            .map(|node| node.data().0)
+            .collect();
+        assert_eq!(keys, vec!["a", "b", "d", "e", "c"]);
+
+        let (empty, ids) = build(Vec::new()).unwrap();
+        assert!(empty.is_empty());
+        assert!(ids.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            build(vec![("a", None), ("b", Some("a")), ("b", Some("a"))]).unwrap_err(),
+            ParentLinksError::DuplicateKey("b")
+        );
+        assert_eq!(
+            build(vec![("a", None), ("b", Some("a")), ("c", None)]).unwrap_err(),
+            ParentLinksError::MultipleRoots {
+                first: "a",
+                second: "c",
+            }
+        );
+        assert_eq!(
+            build(vec![("a", None), ("b", Some("x"))]).unwrap_err(),
+            ParentLinksError::MissingParent {
+                key: "b",
+                parent: "x",
+            }
+        );
+        // c hangs off the b/d cycle, and is reported as one of its members
+        assert_eq!(
+            build(vec![
+                ("a", None),
+                ("c", Some("b")),
+                ("b", Some("d")),
+                ("d", Some("b")),
+            ])
+            .unwrap_err(),
+            ParentLinksError::Cycle("b")
+        );
+        assert_eq!(
+            build(vec![("a", Some("a"))]).unwrap_err(),
+            ParentLinksError::Cycle("a")
+        );
+    }
+
+    #[test]
+    fn test_to_parent_links() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree: Tree<i32> = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let removed = tree.insert(Node::new(9), UnderNode(&root_id)).unwrap();
+        let a = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let orphaned = tree.insert(Node::new(8), UnderNode(&a)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&orphaned)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&a)).unwrap();
+        tree.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+        tree.remove_node(removed, DropChildren).unwrap();
+        tree.remove_node(orphaned, OrphanChildren).unwrap();
+
+        let links: Vec<(Option<i32>, usize, i32)> = tree
+            .to_parent_links()
+            .map(|(_, parent, position, data)| {
+                let parent = parent.map(|parent_id| *tree.get(&parent_id).unwrap().data());
+                (parent, position, *data)
+            })
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                (None, 0, 0),
+                (Some(0), 0, 1),
+                (Some(1), 0, 3),
+                (Some(0), 1, 4)
+            ]
+        );
+
+        let (rebuilt, ids) = Tree::from_parent_links(
+            tree.to_parent_links()
+                .map(|(node_id, parent, _, data)| (node_id, parent, *data)),
+            |row| row.0.clone(),
+            |row| row.1.clone(),
+        )
+        .unwrap();
+        let data: Vec<i32> = rebuilt
+            .to_parent_links()
+            .map(|(_, _, _, row)| row.2)
+            .collect();
+        assert_eq!(data, vec![0, 1, 3, 4]);
+        assert_eq!(ids.len(), 4);
+
+        assert_eq!(Tree::<i32>::new().to_parent_links().count(), 0);
+    }
+}
@@ -0,0 +1,298 @@
+use std::fmt::Display;
+
+use super::*;
+
+impl<T> Tree<T> {
+    ///
+    /// Writes the `Tree` as a Lisp-style S-expression, such as `(root (a b) c)`.
+    ///
+    /// A `Node` with children is written as a list headed by its data, followed by its children.
+    /// A leaf is written as a bare atom.  Atoms are written with `Display`, and put in double quotes
+    /// (with `"` and `\` escaped by a `\`) if they are empty or contain whitespace, parentheses or
+    /// double quotes.  An empty `Tree` is written as an empty `String`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<&str> = Tree::new();
+    /// let root_id = tree.insert(Node::new("root"), AsRoot).unwrap();
+    /// let a_id = tree.insert(Node::new("a"), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new("b"), UnderNode(&a_id)).unwrap();
+    /// tree.insert(Node::new("c d"), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert_eq!(tree.to_sexpr(), "(root (a b) \"c d\")");
+    /// ```
+    ///
+    pub fn to_sexpr(&self) -> String
+    where
+        T: Display,
+    {
+        let mut s = String::new();
+        let root_id = match self.root_node_id() {
+            Some(root_id) => root_id,
+            None => return s,
+        };
+
+        let mut stack = vec![Some(root_id)];
+        while let Some(step) = stack.pop() {
+            // `None` closes the list of the Node entered before it
+            let node_id = match step {
+                Some(node_id) => node_id,
+                None => {
+                    s.push(')');
+                    continue;
+                }
+            };
+            let node = self.get_unsafe(node_id);
+
+            if !s.is_empty() && !s.ends_with('(') {
+                s.push(' ');
+            }
+            if !node.children().is_empty() {
+                s.push('(');
+                stack.push(None);
+                stack.extend(node.children().iter().rev().map(Some));
+            }
+            write_atom(&mut s, &node.data().to_string());
+        }
+
+        s
+    }
+
+    ///
+    /// Reads a `Tree` from a Lisp-style S-expression, such as `(root (a b) c)`, turning each atom
+    /// into a `Node`'s data with `parse_atom`.
+    ///
+    /// The first atom of every list is the `Node` and the rest are its children, while a bare atom
+    /// is a leaf (so `root` and `(root)` are the same).  Atoms may be put in double quotes, inside
+    /// which `\` escapes the next character.  Input that is nothing but whitespace gives an empty
+    /// `Tree`.
+    ///
+    /// Returns a `SexprError` if the input isn't a single S-expression of that shape, or if
+    /// `parse_atom` fails.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = Tree::from_sexpr("(1 (2 3) 4)", |atom| atom.parse()).unwrap();
+    /// assert_eq!(tree.to_sexpr(), "(1 (2 3) 4)");
+    ///
+    /// let error = Tree::<i32>::from_sexpr("(1 ((2) 3))", |atom| atom.parse()).unwrap_err();
+    /// assert_eq!(error.position(), 4);
+    /// ```
+    ///
+    pub fn from_sexpr<F, E>(text: &str, mut parse_atom: F) -> Result<Tree<T>, SexprError<E>>
+    where
+        F: FnMut(&str) -> Result<T, E>,
+    {
+        let mut tree = Tree::new();
+        let mut reader = Reader { text, position: 0 };
+        // the Nodes whose lists are still open
+        let mut open: Vec<NodeId> = Vec::new();
+
+        loop {
+            reader.skip_whitespace();
+            if open.is_empty() && tree.root.is_some() {
+                return match reader.peek() {
+                    Some(found) => Err(SexprError::UnexpectedChar {
+                        position: reader.position,
+                        found,
+                    }),
+                    None => Ok(tree),
+                };
+            }
+
+            let starts_list = match reader.peek() {
+                None if open.is_empty() => return Ok(tree),
+                None => {
+                    return Err(SexprError::UnexpectedEnd {
+                        position: reader.position,
+                    })
+                }
+                Some(')') if !open.is_empty() => {
+                    reader.position += 1;
+                    open.pop();
+                    continue;
+                }
+                Some(')') => {
+                    return Err(SexprError::UnexpectedChar {
+                        position: reader.position,
+                        found: ')',
+                    })
+                }
+                Some('(') => {
+                    let position = reader.position;
+                    reader.position += 1;
+                    reader.skip_whitespace();
+                    match reader.peek() {
+                        Some(')') => return Err(SexprError::EmptyList { position }),
+                        Some('(') => {
+                            return Err(SexprError::UnexpectedChar {
+                                position: reader.position,
+                                found: '(',
+                            })
+                        }
+                        _ => {}
+                    }
+                    true
+                }
+                Some(_) => false,
+            };
+
+            let position = reader.position;
+            let atom = reader.read_atom()?;
+            let data = parse_atom(&atom).map_err(|error| SexprError::Atom { position, error })?;
+
+            let behavior = match open.last() {
+                Some(parent_id) => InsertBehavior::UnderNode(parent_id),
+                None => InsertBehavior::AsRoot,
+            };
+            let node_id = tree
+                .insert(Node::new(data), behavior)
+                .expect("Tree::from_sexpr: The parent is still in the Tree.");
+            if starts_list {
+                open.push(node_id);
+            }
+        }
+    }
+}
+
+fn write_atom(s: &mut String, atom: &str) {
+    let needs_quotes = atom.is_empty() || atom.chars().any(|c| is_delimiter(c) || c == '"');
+    if !needs_quotes {
+        s.push_str(atom);
+        return;
+    }
+
+    s.push('"');
+    for c in atom.chars() {
+        if c == '"' || c == '\\' {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s.push('"');
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn read_atom<E>(&mut self) -> Result<String, SexprError<E>> {
+        let rest = &self.text[self.position..];
+
+        if !rest.starts_with('"') {
+            let len = rest.find(is_delimiter).unwrap_or(rest.len());
+            self.position += len;
+            return Ok(rest[..len].to_string());
+        }
+
+        let mut atom = String::new();
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(atom);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => atom.push(escaped),
+                    None => break,
+                },
+                c => atom.push(c),
+            }
+        }
+
+        Err(SexprError::UnexpectedEnd {
+            position: self.text.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod sexpr_tests {
+    use super::super::*;
+
+    fn parse(text: &str) -> Result<Tree<String>, SexprError<()>> {
+        Tree::from_sexpr(text, |atom| Ok(atom.to_string()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for text in &[
+            "(root (a b) c)",
+            "leaf",
+            "(a (b (c (d e))))",
+            "(\"\" \"x y\" \"(\" \"say \\\"hi\\\"\" back\\slash)",
+        ] {
+            assert_eq!(parse(text).unwrap().to_sexpr(), *text);
+        }
+
+        assert_eq!(Tree::<i32>::new().to_sexpr(), "");
+        assert!(parse("  \n").unwrap().root_node_id().is_none());
+    }
+
+    #[test]
+    fn test_shapes() {
+        let tree = parse("\n( root\n  (a)\t(b c )  )  ").unwrap();
+        assert_eq!(tree.to_sexpr(), "(root a (b c))");
+        assert_eq!(tree.height(), 3);
+
+        let tree = parse("(\"a\\\\b\" \"\")").unwrap();
+        let root_id = tree.root_node_id().unwrap();
+        assert_eq!(tree.get(root_id).unwrap().data(), "a\\b");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("()"), Err(SexprError::EmptyList { position: 0 }));
+        assert_eq!(
+            parse("(a (b)"),
+            Err(SexprError::UnexpectedEnd { position: 6 })
+        );
+        assert_eq!(
+            parse("(a \"b)"),
+            Err(SexprError::UnexpectedEnd { position: 6 })
+        );
+        assert_eq!(
+            parse("a b"),
+            Err(SexprError::UnexpectedChar {
+                position: 2,
+                found: 'b',
+            })
+        );
+        assert_eq!(
+            parse("(a))"),
+            Err(SexprError::UnexpectedChar {
+                position: 3,
+                found: ')',
+            })
+        );
+        assert_eq!(
+            parse(")"),
+            Err(SexprError::UnexpectedChar {
+                position: 0,
+                found: ')',
+            })
+        );
+
+        let error = Tree::<u8>::from_sexpr("(1 x)", |atom| atom.parse()).unwrap_err();
+        assert_eq!(error.position(), 3);
+    }
+}
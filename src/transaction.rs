@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+use journal::Changeset;
+
+use super::*;
+
+///
+/// A handle for making changes to a `Tree` as part of `Tree::transaction`.
+///
+/// A `Transaction` offers the same mutating methods as `Tree` and dereferences to the `Tree`
+/// itself for everything else.  If the transaction is rolled back, every change made through it
+/// is undone, and every `NodeId` that was valid beforehand is valid again and refers to the same
+/// `Node`.
+///
+/// Because removed data has to be restored on rollback, `remove_node` and `replace_data` don't
+/// hand the old data back to the caller; it is dropped once the transaction succeeds.
+///
+pub struct Transaction<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    changes: Changeset<T>,
+}
+
+impl<'a, T> Transaction<'a, T> {
+    pub(crate) fn new(tree: &'a mut Tree<T>) -> Transaction<'a, T> {
+        let changes = Changeset::new(tree);
+        Transaction { tree, changes }
+    }
+
+    pub(crate) fn rollback(mut self) {
+        self.changes.revert(self.tree);
+    }
+
+    ///
+    /// Inserts a new `Node` into the `Tree`, as with `Tree::insert`.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let child_id = tree
+    ///     .transaction(|tx| tx.insert(Node::new(1), UnderNode(&root_id)))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(tree.get(&child_id).unwrap().data(), &1);
+    /// ```
+    ///
+    pub fn insert(
+        &mut self,
+        node: Node<T>,
+        behavior: InsertBehavior,
+    ) -> Result<NodeId, NodeIdError> {
+        self.changes.insert(self.tree, node, behavior)
+    }
+
+    ///
+    /// Removes a `Node` from the `Tree`, as with `Tree::remove_node`.
+    ///
+    /// The removed `Node` isn't returned, since it may still have to be put back.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let result: Result<(), NodeIdError> = tree.transaction(|tx| {
+    ///     tx.remove_node(child_id.clone(), DropChildren)?;
+    ///     tx.remove_node(child_id.clone(), DropChildren)
+    /// });
+    ///
+    /// assert!(result.is_err());
+    /// assert_eq!(tree.get(&child_id).unwrap().data(), &1);
+    /// ```
+    ///
+    pub fn remove_node(
+        &mut self,
+        node_id: NodeId,
+        behavior: RemoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.changes.remove_node(self.tree, node_id, behavior)
+    }
+
+    ///
+    /// Moves a `Node` to a new location in the `Tree`, as with `Tree::move_node`.
+    ///
+    pub fn move_node(
+        &mut self,
+        node_id: &NodeId,
+        behavior: MoveBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.changes.move_node(self.tree, node_id, behavior)
+    }
+
+    ///
+    /// Swaps two `Node`s in the `Tree`, as with `Tree::swap_nodes`.
+    ///
+    pub fn swap_nodes(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+        behavior: SwapBehavior,
+    ) -> Result<(), NodeIdError> {
+        self.changes
+            .swap_nodes(self.tree, first_id, second_id, behavior)
+    }
+
+    ///
+    /// Sorts the children of a `Node`, as with `Tree::sort_children_by`.
+    ///
+    pub fn sort_children_by<F>(&mut self, node_id: &NodeId, compare: F) -> Result<(), NodeIdError>
+    where
+        F: FnMut(&Node<T>, &Node<T>) -> Ordering,
+    {
+        self.changes.sort_children_by(self.tree, node_id, compare)
+    }
+
+    ///
+    /// Sorts the children of a `Node` by their data, as with `Tree::sort_children_by_data`.
+    ///
+    pub fn sort_children_by_data(&mut self, node_id: &NodeId) -> Result<(), NodeIdError>
+    where
+        T: Ord,
+    {
+        self.changes.sort_children_by_data(self.tree, node_id)
+    }
+
+    ///
+    /// Sorts the children of a `Node` by a key, as with `Tree::sort_children_by_key`.
+    ///
+    pub fn sort_children_by_key<B, F>(&mut self, node_id: &NodeId, f: F) -> Result<(), NodeIdError>
+    where
+        B: Ord,
+        F: FnMut(&Node<T>) -> B,
+    {
+        self.changes.sort_children_by_key(self.tree, node_id, f)
+    }
+
+    ///
+    /// Moves a `Node` to a position amongst its siblings, as with `Tree::make_nth_sibling`.
+    ///
+    pub fn make_nth_sibling(&mut self, node_id: &NodeId, pos: usize) -> Result<(), NodeIdError> {
+        self.changes.make_nth_sibling(self.tree, node_id, pos)
+    }
+
+    ///
+    /// Makes a `Node` the first of its siblings, as with `Tree::make_first_sibling`.
+    ///
+    pub fn make_first_sibling(&mut self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        self.changes.make_first_sibling(self.tree, node_id)
+    }
+
+    ///
+    /// Makes a `Node` the last of its siblings, as with `Tree::make_last_sibling`.
+    ///
+    pub fn make_last_sibling(&mut self, node_id: &NodeId) -> Result<bool, NodeIdError> {
+        self.changes.make_last_sibling(self.tree, node_id)
+    }
+
+    ///
+    /// Replaces the data of a `Node`, as with `Node::replace_data`.
+    ///
+    /// The old data isn't returned, since it may still have to be put back.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    ///
+    /// let result: Result<(), &str> = tree.transaction(|tx| {
+    ///     tx.replace_data(&root_id, 1).unwrap();
+    ///     Err("changed my mind")
+    /// });
+    ///
+    /// assert!(result.is_err());
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &0);
+    /// ```
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<(), NodeIdError> {
+        self.changes.replace_data(self.tree, node_id, data)
+    }
+}
+
+impl<'a, T> Deref for Transaction<'a, T> {
+    type Target = Tree<T>;
+
+    fn deref(&self) -> &Tree<T> {
+        self.tree
+    }
+}
+
+#[cfg(test)]
+mod transaction_tests {
+    use super::super::*;
+
+    fn assert_identical(tree: &Tree<i32>, other: &Tree<i32>) {
+        assert_eq!(tree.root, other.root);
+        assert_eq!(tree.free_ids, other.free_ids);
+        assert_eq!(tree.nodes.len(), other.nodes.len());
+        for (node, other) in tree.nodes.iter().zip(other.nodes.iter()) {
+            match (node, other) {
+                (Some(node), Some(other)) => {
+                    assert_eq!(node.data(), other.data());
+                    assert_eq!(node.parent(), other.parent());
+                    assert_eq!(node.children(), other.children());
+                }
+                (None, None) => {}
+                _ => panic!("slots differ"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [node_1 @ 1 => [node_3 @ 3, node_4 @ 4], node_2 @ 2]);
+
+        let result: Result<NodeId, NodeIdError> = tree.transaction(|tx| {
+            tx.remove_node(node_3.clone(), DropChildren)?;
+            tx.move_node(&node_4, ToParent(&node_2))?;
+            tx.insert(Node::new(5), UnderNode(&node_1))
+        });
+
+        let node_5 = result.unwrap();
+        assert!(tree
+            .traverse_pre_order(&root_id)
+            .unwrap()
+            .all(|node| node.data() != &3));
+        assert_eq!(tree.get(&node_4).unwrap().parent(), Some(&node_2));
+        assert_eq!(tree.get(&node_1).unwrap().children(), &vec![node_5]);
+    }
+
+    #[test]
+    fn test_rollback() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+        use SwapBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [node_1 @ 1 => [node_3 @ 3, node_4 @ 4], node_2 @ 2]);
+        tree.remove_node(node_4.clone(), DropChildren).unwrap();
+        let before = tree.clone();
+
+        let result: Result<(), NodeIdError> = tree.transaction(|tx| {
+            let node_5 = tx.insert(Node::new(5), UnderNode(&node_2))?;
+            tx.insert(Node::new(6), UnderNode(&node_5))?;
+            tx.swap_nodes(&node_1, &node_2, TakeChildren)?;
+            tx.move_node(&node_3, ToRoot)?;
+            tx.replace_data(&root_id, 10)?;
+            tx.sort_children_by(&root_id, |a, b| b.data().cmp(a.data()))?;
+            tx.remove_node(node_1.clone(), LiftChildren)?;
+            tx.remove_node(node_5, DropChildren)?;
+            tx.remove_node(node_1.clone(), DropChildren)
+        });
+
+        assert_eq!(result, Err(NodeIdError::NodeIdNoLongerValid));
+        assert_identical(&tree, &before);
+    }
+
+    #[test]
+    fn test_rollback_root_changes() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [1 => [3, 4], 2]);
+        let before = tree.clone();
+
+        let result: Result<(), ()> = tree.transaction(|tx| {
+            tx.remove_node(root_id.clone(), DropChildren).unwrap();
+            tx.insert(Node::new(7), AsRoot).unwrap();
+            tx.insert(Node::new(8), AsRoot).unwrap();
+            Err(())
+        });
+
+        assert!(result.is_err());
+        assert_identical(&tree, &before);
+    }
+
+    #[test]
+    fn test_rollback_leaked_id() {
+        use InsertBehavior::*;
+
+        tree!(let mut tree = 0 => [1 => [3, 4], node_2 @ 2]);
+
+        let result: Result<(), NodeId> = tree.transaction(|tx| {
+            let node_5 = tx.insert(Node::new(5), UnderNode(&node_2)).unwrap();
+            Err(node_5)
+        });
+
+        let node_5 = result.unwrap_err();
+        assert_eq!(
+            tree.get(&node_5).err(),
+            Some(NodeIdError::NodeIdNoLongerValid)
+        );
+        assert!(tree.get_mut(&node_5).is_err());
+    }
+}
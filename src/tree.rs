@@ -1,14 +1,19 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::{Index, IndexMut};
 
 use super::*;
+use observer::Observers;
 
 ///
 /// A `Tree` builder that provides more control over how a `Tree` is created.
 ///
 pub struct TreeBuilder<T> {
-    root: Option<Node<T>>,
-    node_capacity: usize,
-    swap_capacity: usize,
+    pub(crate) root: Option<Node<T>>,
+    pub(crate) node_capacity: usize,
+    pub(crate) swap_capacity: usize,
 }
 
 impl<T> TreeBuilder<T> {
@@ -122,6 +127,8 @@ impl<T> TreeBuilder<T> {
             root: None,
             nodes: Vec::with_capacity(self.node_capacity),
             free_ids: Vec::with_capacity(self.swap_capacity),
+            observers: Observers::default(),
+            self_check: false,
         };
 
         if self.root.is_some() {
@@ -148,10 +155,40 @@ impl<T> TreeBuilder<T> {
 ///
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(try_from = "RawTree<T>"))]
 pub struct Tree<T> {
-    root: Option<NodeId>,
+    pub(crate) root: Option<NodeId>,
     pub(crate) nodes: Vec<Option<Node<T>>>,
-    free_ids: Vec<NodeId>,
+    pub(crate) free_ids: Vec<NodeId>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub(crate) observers: Observers,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    self_check: bool,
+}
+
+///
+/// How many bytes of heap storage a `Tree` is using, as reported by `Tree::memory_stats`.
+///
+/// Only the `Tree`'s own storage is counted, based on the capacity (rather than the length) of
+/// each buffer.  Anything a `Node`'s data owns on the heap isn't included.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryStats {
+    /// The storage holding the `Node`s themselves, including empty slots.
+    pub nodes: usize,
+    /// The storage holding the `NodeId`s of every `Node`'s children.
+    pub children: usize,
+    /// The storage holding the `NodeId`s of empty slots waiting to be reused.
+    pub free_ids: usize,
+}
+
+impl MemoryStats {
+    ///
+    /// Returns the total number of bytes.
+    ///
+    pub fn total(&self) -> usize {
+        self.nodes + self.children + self.free_ids
+    }
 }
 
 impl<T> Tree<T> {
@@ -175,6 +212,152 @@ impl<T> Tree<T> {
         self.nodes.capacity()
     }
 
+    ///
+    /// Reserves room for at least `additional` more `Node`s to be inserted without reallocating.
+    ///
+    /// ```
+    /// use id_tree::Tree;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// tree.reserve(10);
+    /// assert!(tree.capacity() >= 10);
+    /// ```
+    ///
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    ///
+    /// Gives back as much unused storage as possible without changing any `NodeId`s.
+    ///
+    /// Only the empty slots at the very end of the `Tree`'s storage can be dropped; use
+    /// `Tree::compact` to get rid of the rest.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = TreeBuilder::new().with_node_capacity(10).build();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.remove_node(child_id, DropChildren).unwrap();
+    ///
+    /// tree.shrink_to_fit();
+    /// assert_eq!(tree.capacity(), 1);
+    /// ```
+    ///
+    pub fn shrink_to_fit(&mut self) {
+        let len = self
+            .nodes
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |index| index + 1);
+        self.nodes.truncate(len);
+        self.free_ids.retain(|free_id| free_id.index < len);
+
+        self.nodes.shrink_to_fit();
+        self.free_ids.shrink_to_fit();
+        for node in self.nodes.iter_mut().flatten() {
+            node.children.shrink_to_fit();
+        }
+    }
+
+    ///
+    /// Returns the number of `Node`s in the `Tree`, including any that have been orphaned.
+    ///
+    /// This takes constant time.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// assert_eq!(tree.len(), 2);
+    ///
+    /// tree.remove_node(child_id, DropChildren).unwrap();
+    /// assert_eq!(tree.len(), 1);
+    /// assert_eq!(tree.free_slot_count(), 1);
+    /// ```
+    ///
+    pub fn len(&self) -> usize {
+        // every empty slot is listed in `free_ids`, exactly once
+        self.nodes.len() - self.free_ids.len()
+    }
+
+    ///
+    /// Returns `true` if the `Tree` has no `Node`s at all.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// Returns the number of slots left empty by removed `Node`s, which will be reused before the
+    /// `Tree` grows.
+    ///
+    pub fn free_slot_count(&self) -> usize {
+        self.free_ids.len()
+    }
+
+    ///
+    /// Returns the number of `Node`s in the sub-tree of the given `Node`, including the `Node`
+    /// itself.
+    ///
+    /// Returns a `NodeIdError` if the `NodeId` is no longer valid.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+    ///
+    /// assert_eq!(tree.subtree_size(&root_id), Ok(3));
+    /// assert_eq!(tree.subtree_size(&child_id), Ok(2));
+    /// ```
+    ///
+    pub fn subtree_size(&self, node_id: &NodeId) -> Result<usize, NodeIdError> {
+        Ok(self.traverse_pre_order_ids(node_id)?.count())
+    }
+
+    ///
+    /// Reports how many bytes of heap storage the `Tree` is using.
+    ///
+    /// Unlike `Tree::len` and `Tree::free_slot_count`, this is not kept up to date as the `Tree`
+    /// changes: it takes O(n) time, visiting every `Node` to add up the storage used for their
+    /// children.  Any `Node` handed out by `Tree::get_mut` can be replaced outright, children and
+    /// all, so a running total could not be trusted.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<u64> = TreeBuilder::new().with_node_capacity(4).build();
+    /// let stats = tree.memory_stats();
+    ///
+    /// assert_eq!(stats.nodes, 4 * std::mem::size_of::<Option<Node<u64>>>());
+    /// assert_eq!(stats.total(), stats.nodes);
+    /// ```
+    ///
+    pub fn memory_stats(&self) -> MemoryStats {
+        let id_size = mem::size_of::<NodeId>();
+        MemoryStats {
+            nodes: self.nodes.capacity() * mem::size_of::<Option<Node<T>>>(),
+            children: self
+                .nodes
+                .iter()
+                .flatten()
+                .map(|node| node.children.capacity() * id_size)
+                .sum(),
+            free_ids: self.free_ids.capacity() * id_size,
+        }
+    }
+
     ///
     /// Returns the maximum height of the `Tree`.
     ///
@@ -208,6 +391,134 @@ impl<T> Tree<T> {
         h + 1
     }
 
+    ///
+    /// Returns `true` if this `Tree` and `other` have the same shape and the same data in every
+    /// corresponding `Node`.
+    ///
+    /// Both `Tree`s are walked from their root `Node`s, comparing data and then the children of
+    /// each `Node` in order.  The `NodeId`s themselves (and therefore the order in which the `Node`s
+    /// were inserted or removed) play no part in the comparison.  `Node`s that cannot be reached
+    /// from the root (for example, those orphaned by `RemoveBehavior::OrphanChildren`) are ignored.
+    ///
+    /// This is what `Tree`'s `PartialEq` implementation uses.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::RemoveBehavior::*;
+    ///
+    /// let mut a: Tree<i32> = Tree::new();
+    /// let root_id = a.insert(Node::new(0), AsRoot).unwrap();
+    /// a.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut b: Tree<i32> = Tree::new();
+    /// let root_id = b.insert(Node::new(0), AsRoot).unwrap();
+    /// let extra_id = b.insert(Node::new(42), UnderNode(&root_id)).unwrap();
+    /// b.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// b.remove_node(extra_id, DropChildren).unwrap();
+    ///
+    /// assert!(a.structurally_eq(&b));
+    /// ```
+    ///
+    pub fn structurally_eq(&self, other: &Tree<T>) -> bool
+    where
+        T: PartialEq,
+    {
+        match (self.root_node_id(), other.root_node_id()) {
+            (Some(root), Some(other_root)) => self.subtree_eq(root, other, other_root),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    ///
+    /// Returns `true` if this `Tree` and `other` are equal when the order of each `Node`'s children
+    /// is disregarded.
+    ///
+    /// This behaves like `structurally_eq`, except that the children of two corresponding `Node`s
+    /// only have to match each other as a multiset.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut a: Tree<i32> = Tree::new();
+    /// let root_id = a.insert(Node::new(0), AsRoot).unwrap();
+    /// a.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// a.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// let mut b: Tree<i32> = Tree::new();
+    /// let root_id = b.insert(Node::new(0), AsRoot).unwrap();
+    /// b.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    /// b.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert!(!a.structurally_eq(&b));
+    /// assert!(a.structurally_eq_unordered(&b));
+    /// ```
+    ///
+    pub fn structurally_eq_unordered(&self, other: &Tree<T>) -> bool
+    where
+        T: PartialEq,
+    {
+        match (self.root_node_id(), other.root_node_id()) {
+            (Some(root), Some(other_root)) => self.subtree_eq_unordered(root, other, other_root),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn subtree_eq(&self, node_id: &NodeId, other: &Tree<T>, other_id: &NodeId) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut stack = vec![(node_id, other_id)];
+        while let Some((id, other_id)) = stack.pop() {
+            let node = self.get_unsafe(id);
+            let other_node = other.get_unsafe(other_id);
+
+            if node.data() != other_node.data()
+                || node.children().len() != other_node.children().len()
+            {
+                return false;
+            }
+
+            stack.extend(node.children().iter().zip(other_node.children().iter()));
+        }
+
+        true
+    }
+
+    fn subtree_eq_unordered(&self, node_id: &NodeId, other: &Tree<T>, other_id: &NodeId) -> bool
+    where
+        T: PartialEq,
+    {
+        let node = self.get_unsafe(node_id);
+        let other_node = other.get_unsafe(other_id);
+
+        if node.data() != other_node.data() || node.children().len() != other_node.children().len()
+        {
+            return false;
+        }
+
+        // Equality is an equivalence relation, so greedily pairing each child with the first
+        // unused match can never rule out a complete pairing that exists.
+        let mut unmatched: Vec<&NodeId> = other_node.children().iter().collect();
+        for child_id in node.children() {
+            let position = unmatched.iter().position(|other_child_id| {
+                self.subtree_eq_unordered(child_id, other, other_child_id)
+            });
+
+            match position {
+                Some(position) => {
+                    unmatched.swap_remove(position);
+                }
+                None => return false,
+            }
+        }
+
+        true
+    }
+
     /// Inserts a new `Node` into the `Tree`.  The `InsertBehavior` provided will determine where
     /// the `Node` is inserted.
     ///
@@ -232,7 +543,7 @@ impl<T> Tree<T> {
         node: Node<T>,
         behavior: InsertBehavior,
     ) -> Result<NodeId, NodeIdError> {
-        match behavior {
+        let node_id = match behavior {
             InsertBehavior::UnderNode(parent_id) => {
                 let (is_valid, error) = self.is_valid_node_id(parent_id);
                 if !is_valid {
@@ -241,10 +552,28 @@ impl<T> Tree<T> {
                          invalid NodeId.",
                     ));
                 }
-                self.insert_with_parent(node, parent_id)
+                self.insert_with_parent(node, parent_id)?
             }
-            InsertBehavior::AsRoot => Ok(self.set_root(node)),
+            InsertBehavior::AsRoot => self.set_root(node),
+        };
+
+        let parent = self.get_unsafe(&node_id).parent().cloned();
+        let pos = parent.as_ref().map_or(0, |parent_id| {
+            self.get_unsafe(parent_id).children().len() - 1
+        });
+        self.notify(|| TreeEvent::Inserted {
+            id: node_id.clone(),
+            parent,
+            pos,
+        });
+
+        // a new root takes the old root as its child
+        if let InsertBehavior::AsRoot = behavior {
+            let old_root = self.get_unsafe(&node_id).children().first().cloned();
+            self.notify_displaced(old_root.map(|old_root_id| (old_root_id, None)));
         }
+
+        Ok(node_id)
     }
 
     ///
@@ -325,6 +654,160 @@ impl<T> Tree<T> {
         }
     }
 
+    ///
+    /// Get mutable references to several `Node`s at once.
+    ///
+    /// Returns a `GetManyMutError` if any of the `NodeId`s is no longer valid, or if the same
+    /// `NodeId` is given more than once.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+    /// let first_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let second_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// let [root, first, second] = tree.get_many_mut([&root_id, &first_id, &second_id]).unwrap();
+    /// *root.data_mut() -= first.data() + second.data();
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &2);
+    ///
+    /// let error = tree.get_many_mut([&first_id, &root_id, &first_id]).unwrap_err();
+    /// assert_eq!(error, GetManyMutError::Duplicate { first: 0, second: 2 });
+    /// ```
+    ///
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        node_ids: [&NodeId; N],
+    ) -> Result<[&mut Node<T>; N], GetManyMutError> {
+        for (position, node_id) in node_ids.iter().enumerate() {
+            if !self.is_valid_node_id(node_id).0 {
+                return Err(GetManyMutError::NodeIdNoLongerValid { position });
+            }
+            if let Some(first) = node_ids[..position].iter().position(|id| id == node_id) {
+                return Err(GetManyMutError::Duplicate {
+                    first,
+                    second: position,
+                });
+            }
+        }
+
+        // hand out the slots in order of their indices, splitting each one off the rest
+        let mut order = [0; N];
+        for (position, entry) in order.iter_mut().enumerate() {
+            *entry = position;
+        }
+        order.sort_unstable_by_key(|&position| node_ids[position].index);
+
+        let mut nodes: [Option<&mut Node<T>>; N] = [(); N].map(|_| None);
+        let mut rest = &mut self.nodes[..];
+        let mut offset = 0;
+        for &position in &order {
+            let index = node_ids[position].index;
+            let (slot, tail) = mem::take(&mut rest)[index - offset..]
+                .split_first_mut()
+                .expect("Tree::get_many_mut: The NodeIds were just checked.");
+            nodes[position] = slot.as_mut();
+            rest = tail;
+            offset = index + 1;
+        }
+        Ok(nodes.map(|node| node.expect("Tree::get_many_mut: The NodeIds were just checked.")))
+    }
+
+    ///
+    /// Get mutable references to two different `Node`s at once, such as to move data between a
+    /// parent and its child.
+    ///
+    /// Returns a `GetManyMutError` if either `NodeId` is no longer valid, or if they are the same.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<Vec<i32>> = Tree::new();
+    /// let root_id = tree.insert(Node::new(vec![1, 2]), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(vec![]), UnderNode(&root_id)).unwrap();
+    ///
+    /// let (root, child) = tree.get_pair_mut(&root_id, &child_id).unwrap();
+    /// child.data_mut().append(root.data_mut());
+    ///
+    /// assert_eq!(tree.get(&child_id).unwrap().data(), &vec![1, 2]);
+    /// assert!(tree.get_pair_mut(&root_id, &root_id).is_err());
+    /// ```
+    ///
+    pub fn get_pair_mut(
+        &mut self,
+        first_id: &NodeId,
+        second_id: &NodeId,
+    ) -> Result<(&mut Node<T>, &mut Node<T>), GetManyMutError> {
+        let [first, second] = self.get_many_mut([first_id, second_id])?;
+        Ok((first, second))
+    }
+
+    ///
+    /// Get an immutable reference to the data of a `Node`.
+    ///
+    /// Returns a `Result` containing the immutable reference or a `NodeIdError` if one occurred.
+    ///
+    /// ```
+    /// use id_tree::*;
+    ///
+    /// let tree: Tree<i32> = TreeBuilder::new().with_root(Node::new(5)).build();
+    /// let root_id = tree.root_node_id().unwrap();
+    ///
+    /// assert_eq!(tree.get_data(root_id), Ok(&5));
+    /// ```
+    ///
+    pub fn get_data(&self, node_id: &NodeId) -> Result<&T, NodeIdError> {
+        self.get(node_id).map(Node::data)
+    }
+
+    ///
+    /// Get a mutable reference to the data of a `Node`.
+    ///
+    /// Returns a `Result` containing the mutable reference or a `NodeIdError` if one occurred.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+    ///
+    /// *tree.get_data_mut(&root_id).unwrap() += 1;
+    /// assert_eq!(tree.get_data(&root_id), Ok(&6));
+    /// ```
+    ///
+    pub fn get_data_mut(&mut self, node_id: &NodeId) -> Result<&mut T, NodeIdError> {
+        self.get_mut(node_id).map(Node::data_mut)
+    }
+
+    ///
+    /// Replaces the data of a `Node`, returning the old data.
+    ///
+    /// This does the same as calling `Node::replace_data` through `get_mut`, except that the
+    /// `Tree`'s observers are told about it.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+    ///
+    /// assert_eq!(tree.replace_data(&root_id, 6), Ok(5));
+    /// assert_eq!(tree.get(&root_id).unwrap().data(), &6);
+    /// ```
+    ///
+    pub fn replace_data(&mut self, node_id: &NodeId, data: T) -> Result<T, NodeIdError> {
+        let old = self.get_mut(node_id)?.replace_data(data);
+        self.notify(|| TreeEvent::DataReplaced {
+            id: node_id.clone(),
+        });
+        Ok(old)
+    }
+
     /// Remove a `Node` from the `Tree`.  The `RemoveBehavior` provided determines what happens to
     /// the removed `Node`'s children.
     ///
@@ -372,11 +855,18 @@ impl<T> Tree<T> {
             ));
         }
 
-        match behavior {
-            RemoveBehavior::DropChildren => self.remove_node_drop_children(node_id),
-            RemoveBehavior::LiftChildren => self.remove_node_lift_children(node_id),
-            RemoveBehavior::OrphanChildren => self.remove_node_orphan_children(node_id),
-        }
+        let node = match behavior {
+            RemoveBehavior::DropChildren => self.remove_node_drop_children(node_id.clone()),
+            RemoveBehavior::LiftChildren => self.remove_node_lift_children(node_id.clone()),
+            RemoveBehavior::OrphanChildren => self.remove_node_orphan_children(node_id.clone()),
+        }?;
+
+        self.notify(|| TreeEvent::Removed {
+            id: node_id,
+            behavior,
+        });
+
+        Ok(node)
     }
 
     ///
@@ -453,19 +943,67 @@ impl<T> Tree<T> {
             ));
         }
 
-        match behavior {
-            MoveBehavior::ToRoot => self.move_node_to_root(node_id),
-            MoveBehavior::ToParent(parent_id) => {
-                let (is_valid, error) = self.is_valid_node_id(parent_id);
-                if !is_valid {
-                    return Err(error.expect(
-                        "Tree::move_node: Missing an error value on finding \
-                         an invalid NodeId.",
-                    ));
-                }
-                self.move_node_to_parent(node_id, parent_id)
+        if let MoveBehavior::ToParent(parent_id) = behavior {
+            let (is_valid, error) = self.is_valid_node_id(parent_id);
+            if !is_valid {
+                return Err(error.expect(
+                    "Tree::move_node: Missing an error value on finding \
+                     an invalid NodeId.",
+                ));
             }
         }
+
+        let old_parent = self.get_unsafe(node_id).parent().cloned();
+
+        // Moving a Node beneath one of its own descendants re-attaches the child it is moved
+        // through to its old parent, and moving a Node to the root puts the old root beneath it.
+        let displaced = match behavior {
+            MoveBehavior::ToRoot => self.root.clone().filter(|root_id| root_id != node_id),
+            MoveBehavior::ToParent(parent_id) => self
+                .find_subtree_root_between_ids(parent_id, node_id)
+                .cloned(),
+        };
+        let displaced = displaced.map(|displaced_id| {
+            let displaced_parent = self.get_unsafe(&displaced_id).parent().cloned();
+            (displaced_id, displaced_parent)
+        });
+
+        match behavior {
+            MoveBehavior::ToRoot => self.move_node_to_root(node_id)?,
+            MoveBehavior::ToParent(parent_id) => self.move_node_to_parent(node_id, parent_id)?,
+        }
+
+        // The events come in an order in which applying them one at a time never makes a cycle.
+        let moving_down = matches!(behavior, MoveBehavior::ToParent(_));
+        if moving_down {
+            self.notify_displaced(displaced.clone());
+        }
+        let new_parent = self.get_unsafe(node_id).parent().cloned();
+        self.notify(|| TreeEvent::Moved {
+            id: node_id.clone(),
+            old_parent,
+            new_parent,
+        });
+        if !moving_down {
+            self.notify_displaced(displaced);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Tells observers about a `Node` that was given a new parent as a side effect of moving or
+    /// inserting another `Node`.
+    ///
+    fn notify_displaced(&mut self, displaced: Option<(NodeId, Option<NodeId>)>) {
+        if let Some((id, old_parent)) = displaced {
+            let new_parent = self.get_unsafe(&id).parent().cloned();
+            self.notify(|| TreeEvent::Moved {
+                id,
+                old_parent,
+                new_parent,
+            });
+        }
     }
 
     /// Moves a `Node` inside a `Tree` to a new parent leaving all children in their place.
@@ -589,6 +1127,7 @@ impl<T> Tree<T> {
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by(|a, b| compare(self.get_unsafe(a), self.get_unsafe(b)));
         self.get_mut_unsafe(node_id).set_children(children);
+        self.notify_children_reordered(node_id);
 
         Ok(())
     }
@@ -633,6 +1172,7 @@ impl<T> Tree<T> {
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by_key(|a| self.get_unsafe(a).data());
         self.get_mut_unsafe(node_id).set_children(children);
+        self.notify_children_reordered(node_id);
 
         Ok(())
     }
@@ -683,6 +1223,7 @@ impl<T> Tree<T> {
         let mut children = self.get_mut_unsafe(node_id).take_children();
         children.sort_by_key(|a| f(self.get_unsafe(a)));
         self.get_mut_unsafe(node_id).set_children(children);
+        self.notify_children_reordered(node_id);
 
         Result::Ok(())
     }
@@ -739,6 +1280,10 @@ impl<T> Tree<T> {
             .find_map(|(i, n)| if n == node { Some(i) } else { None })
             .unwrap();
 
+        if current_pos == pos {
+            return Ok(());
+        }
+
         while current_pos != pos {
             let pos_to_swap = if current_pos < pos {
                 current_pos + 1
@@ -752,9 +1297,10 @@ impl<T> Tree<T> {
                 .nth(pos_to_swap)
                 .unwrap()
                 .clone();
-            self.swap_nodes(node, &node_to_swap, SwapBehavior::TakeChildren)?;
+            self.swap_nodes_take_children(node, &node_to_swap)?;
             current_pos = pos_to_swap;
         }
+        self.notify_children_reordered(&parent);
 
         Ok(())
     }
@@ -809,6 +1355,9 @@ impl<T> Tree<T> {
                 parent.children.swap(position - 1, position);
                 position -= 1;
             }
+            if moved {
+                self.notify_children_reordered(&parent_id);
+            }
         }
         Ok(moved)
     }
@@ -864,6 +1413,9 @@ impl<T> Tree<T> {
                 parent.children.swap(position + 1, position);
                 position += 1;
             }
+            if moved {
+                self.notify_children_reordered(&parent_id);
+            }
         }
         Ok(moved)
     }
@@ -918,7 +1470,15 @@ impl<T> Tree<T> {
             SwapBehavior::TakeChildren => self.swap_nodes_take_children(first_id, second_id),
             SwapBehavior::LeaveChildren => self.swap_nodes_leave_children(first_id, second_id),
             SwapBehavior::ChildrenOnly => self.swap_nodes_children_only(first_id, second_id),
-        }
+        }?;
+
+        self.notify(|| TreeEvent::Swapped {
+            first_id: first_id.clone(),
+            second_id: second_id.clone(),
+            behavior,
+        });
+
+        Ok(())
     }
 
     /// Swaps two `Node`s including their children given their `NodeId`s.
@@ -1197,12 +1757,320 @@ impl<T> Tree<T> {
                     .set_parent(Some(first_id.clone()));
             }
 
-            //swap children of these nodes
-            self.get_mut_unsafe(first_id).set_children(second_children);
-            self.get_mut_unsafe(second_id).set_children(first_children);
+            //swap children of these nodes
+            self.get_mut_unsafe(first_id).set_children(second_children);
+            self.get_mut_unsafe(second_id).set_children(first_children);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Registers an observer that is called with a `TreeEvent` after every change made through
+    /// the `Tree`'s mutation methods.
+    ///
+    /// Changes made directly to a `Node` obtained through `get_mut` bypass the `Tree` and aren't
+    /// reported; use `replace_data` to have data changes reported.
+    ///
+    /// Returns an `ObserverId` that can be passed to `remove_observer`.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    ///
+    /// let inserted = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let sink = inserted.clone();
+    /// tree.add_observer(move |event| {
+    ///     if let TreeEvent::Inserted { ref id, .. } = *event {
+    ///         sink.lock().unwrap().push(id.clone());
+    ///     }
+    /// });
+    ///
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    ///
+    /// assert_eq!(*inserted.lock().unwrap(), vec![root_id, child_id]);
+    /// ```
+    ///
+    pub fn add_observer<F>(&mut self, observer: F) -> ObserverId
+    where
+        F: FnMut(&TreeEvent) + Send + Sync + 'static,
+    {
+        self.observers.add(Box::new(observer))
+    }
+
+    ///
+    /// Unregisters an observer.  Returns `false` if it had already been removed.
+    ///
+    pub fn remove_observer(&mut self, observer_id: ObserverId) -> bool {
+        self.observers.remove(observer_id)
+    }
+
+    ///
+    /// Applies a batch of changes to the `Tree` atomically.
+    ///
+    /// The closure is given a `Transaction` through which it can make changes to the `Tree`.  If
+    /// the closure returns an `Err`, every change it made is rolled back before the error is
+    /// returned: the structure, the data, the root and the `Tree`'s internal bookkeeping are all
+    /// restored, so every `NodeId` that was valid beforehand refers to the same `Node` again.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// let other_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+    ///
+    /// let result = tree.transaction(|tx| {
+    ///     tx.move_node(&child_id, ToParent(&other_id))?;
+    ///     tx.insert(Node::new(3), UnderNode(&child_id))?;
+    ///     tx.make_first_sibling(&root_id)
+    /// });
+    /// # assert!(result.is_ok());
+    ///
+    /// let result: Result<(), NodeIdError> = tree.transaction(|tx| {
+    ///     tx.move_node(&child_id, ToParent(&root_id))?;
+    ///     let removed_id = tx.insert(Node::new(4), UnderNode(&child_id))?;
+    ///     tx.remove_node(removed_id.clone(), RemoveBehavior::DropChildren)?;
+    ///     tx.remove_node(removed_id, RemoveBehavior::DropChildren)
+    /// });
+    ///
+    /// assert!(result.is_err());
+    /// assert_eq!(tree.get(&child_id).unwrap().parent(), Some(&other_id));
+    /// # assert_eq!(tree.get(&child_id).unwrap().children().len(), 1);
+    /// ```
+    ///
+    pub fn transaction<F, R, E>(&mut self, f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Transaction<T>) -> Result<R, E>,
+    {
+        let mut transaction = Transaction::new(self);
+        let result = f(&mut transaction);
+        if result.is_err() {
+            transaction.rollback();
+        }
+        result
+    }
+
+    ///
+    /// Checks the `Tree`'s internal bookkeeping for consistency, returning every problem found.
+    ///
+    /// This checks that parent and child links agree with each other, that no `Node` has the same
+    /// child twice, that no `Node` is its own ancestor, that the root has no parent, that the free
+    /// `NodeId`s are exactly the empty slots, and that every `Node` can be reached from the root or
+    /// from a parentless `Node` (such as one left behind by `RemoveBehavior::OrphanChildren`).
+    ///
+    /// A `Tree` that is only ever changed through its own methods should always be valid, so this
+    /// is meant for tracking down bugs.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::SwapBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.swap_nodes(&root_id, &child_id, LeaveChildren).unwrap();
+    ///
+    /// assert_eq!(tree.validate(), Ok(()));
+    /// ```
+    ///
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        // Nothing about the links can be trusted here, so `nodes` is only ever indexed through
+        // `slot` (or with indices already known to be in bounds).
+        let slot = |node_id: &NodeId| self.nodes.get(node_id.index).and_then(|s| s.as_ref());
+        let mut violations = Vec::new();
+
+        if let Some(ref root_id) = self.root {
+            match slot(root_id) {
+                None => violations.push(InvariantViolation::RootNotInTree(root_id.clone())),
+                Some(root) if root.parent.is_some() => {
+                    violations.push(InvariantViolation::RootHasParent(root_id.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        // Whether each Node is amongst the children of the Node it names as its parent, worked out
+        // up front so that no list of children has to be searched.
+        let mut listed_by_parent = vec![false; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(ref node) = *node {
+                for child_id in &node.children {
+                    let parent_index = slot(child_id)
+                        .and_then(|child| child.parent.as_ref())
+                        .map(|parent_id| parent_id.index);
+                    if parent_index == Some(index) {
+                        listed_by_parent[child_id.index] = true;
+                    }
+                }
+            }
+        }
+
+        // Children seen so far in the current list, cleared again after each list.
+        let mut seen = vec![false; self.nodes.len()];
+        let mut seen_out_of_bounds = HashSet::new();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let node = match *node {
+                Some(ref node) => node,
+                None => continue,
+            };
+            let node_id = NodeId { index };
+
+            if let Some(ref parent_id) = node.parent {
+                match slot(parent_id) {
+                    Some(_) if listed_by_parent[index] => {}
+                    Some(_) => violations.push(InvariantViolation::NotChildOfParent {
+                        node: node_id.clone(),
+                        parent: parent_id.clone(),
+                    }),
+                    None => violations.push(InvariantViolation::ParentNotInTree {
+                        node: node_id.clone(),
+                        parent: parent_id.clone(),
+                    }),
+                }
+            }
+
+            for child_id in &node.children {
+                match slot(child_id) {
+                    Some(child) if child.parent.as_ref() == Some(&node_id) => {}
+                    Some(_) => violations.push(InvariantViolation::ChildHasOtherParent {
+                        node: node_id.clone(),
+                        child: child_id.clone(),
+                    }),
+                    None => violations.push(InvariantViolation::ChildNotInTree {
+                        node: node_id.clone(),
+                        child: child_id.clone(),
+                    }),
+                }
+                let duplicate = match seen.get_mut(child_id.index) {
+                    Some(seen) => mem::replace(seen, true),
+                    None => !seen_out_of_bounds.insert(child_id.index),
+                };
+                if duplicate {
+                    violations.push(InvariantViolation::DuplicateChild {
+                        node: node_id.clone(),
+                        child: child_id.clone(),
+                    });
+                }
+            }
+            for child_id in &node.children {
+                if let Some(seen) = seen.get_mut(child_id.index) {
+                    *seen = false;
+                }
+            }
+            seen_out_of_bounds.clear();
+        }
+
+        // Follow every Node's parents until reaching a parentless Node, a Node already known to
+        // lead to one, or a Node on the current path (a cycle).
+        // 0: not visited yet, 1: on the current path, 2: done
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut in_cycle = vec![false; self.nodes.len()];
+        for start in 0..self.nodes.len() {
+            let mut path = Vec::new();
+            let mut current = Some(start);
+            while let Some(index) = current {
+                let node = match self.nodes.get(index) {
+                    Some(Some(node)) if state[index] != 2 => node,
+                    _ => break,
+                };
+                if state[index] == 1 {
+                    violations.push(InvariantViolation::Cycle(NodeId { index }));
+                    let cycle_start = path.iter().position(|&i| i == index).unwrap();
+                    for &i in &path[cycle_start..] {
+                        in_cycle[i] = true;
+                    }
+                    break;
+                }
+                state[index] = 1;
+                path.push(index);
+                current = node.parent.as_ref().map(|parent_id| parent_id.index);
+            }
+            for index in path {
+                state[index] = 2;
+            }
+        }
+
+        // Walk down from every parentless Node; whatever isn't reached hangs off a cycle.
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].as_ref().is_some_and(|n| n.parent.is_none()))
+            .collect();
+        while let Some(index) = stack.pop() {
+            if reached[index] {
+                continue;
+            }
+            reached[index] = true;
+            let node_id = NodeId { index };
+            for child_id in &self.nodes[index].as_ref().unwrap().children {
+                if slot(child_id).is_some_and(|child| child.parent.as_ref() == Some(&node_id)) {
+                    stack.push(child_id.index);
+                }
+            }
+        }
+        for index in 0..self.nodes.len() {
+            if self.nodes[index].is_some() && !reached[index] && !in_cycle[index] {
+                violations.push(InvariantViolation::Unreachable(NodeId { index }));
+            }
+        }
+
+        let mut free = vec![false; self.nodes.len()];
+        for free_id in &self.free_ids {
+            let violation = match self.nodes.get(free_id.index) {
+                Some(None) if !free[free_id.index] => {
+                    free[free_id.index] = true;
+                    continue;
+                }
+                Some(None) => InvariantViolation::DuplicateFreeId(free_id.clone()),
+                Some(Some(_)) => InvariantViolation::FreeIdInUse(free_id.clone()),
+                None => InvariantViolation::FreeIdOutOfBounds(free_id.clone()),
+            };
+            violations.push(violation);
+        }
+        for (index, slot) in self.nodes.iter().enumerate() {
+            if slot.is_none() && !free[index] {
+                violations.push(InvariantViolation::SlotNotFree(NodeId { index }));
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
         }
+    }
 
-        Ok(())
+    ///
+    /// Turns on (or off) checking the `Tree` with `validate` after every change made through its
+    /// mutation methods, panicking if it has become inconsistent.
+    ///
+    /// This is only done in builds with `debug_assertions` enabled; otherwise this setting has no
+    /// effect.  Since every check looks at the whole `Tree`, this is meant for tests and fuzzing.
+    ///
+    /// ```
+    /// use id_tree::*;
+    /// use id_tree::InsertBehavior::*;
+    /// use id_tree::MoveBehavior::*;
+    ///
+    /// let mut tree: Tree<i32> = Tree::new();
+    /// tree.set_self_check(true);
+    ///
+    /// let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+    /// let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+    /// tree.move_node(&child_id, ToRoot).unwrap();
+    /// ```
+    ///
+    pub fn set_self_check(&mut self, enabled: bool) {
+        self.self_check = enabled;
     }
 
     ///
@@ -1552,11 +2420,9 @@ impl<T> Tree<T> {
     // If there is a way for a NodeId to be invalid, it should be caught here.
     fn is_valid_node_id(&self, node_id: &NodeId) -> (bool, Option<NodeIdError>) {
         if node_id.index >= self.nodes.len() {
-            panic!(
-                "NodeId: {:?} is out of bounds. This is most likely a bug in id_tree. Please \
-                 report this issue!",
-                node_id
-            );
+            // Rolling back or undoing an insertion can shrink `nodes`, leaving NodeIds that point
+            // past the end of it.
+            return (false, Some(NodeIdError::NodeIdNoLongerValid));
         }
 
         unsafe {
@@ -1585,6 +2451,32 @@ impl<T> Tree<T> {
         None
     }
 
+    ///
+    /// Runs the self-check, if enabled, and then tells the observers about a change.
+    ///
+    pub(crate) fn notify<F>(&mut self, event: F)
+    where
+        F: FnOnce() -> TreeEvent,
+    {
+        if cfg!(debug_assertions) && self.self_check {
+            if let Err(violations) = self.validate() {
+                panic!(
+                    "Tree::notify: The Tree is inconsistent after a change: {:?}. Please report \
+                     this issue!",
+                    violations
+                );
+            }
+        }
+
+        self.observers.notify(event);
+    }
+
+    fn notify_children_reordered(&mut self, parent_id: &NodeId) {
+        self.notify(|| TreeEvent::ChildrenReordered {
+            parent: parent_id.clone(),
+        });
+    }
+
     fn set_as_parent_and_child(&mut self, parent_id: &NodeId, child_id: &NodeId) {
         self.get_mut_unsafe(parent_id).add_child(child_id.clone());
 
@@ -1684,7 +2576,7 @@ impl<T> Tree<T> {
         }
     }
 
-    fn get_mut_unsafe(&mut self, node_id: &NodeId) -> &mut Node<T> {
+    pub(crate) fn get_mut_unsafe(&mut self, node_id: &NodeId) -> &mut Node<T> {
         unsafe {
             self.nodes.get_unchecked_mut(node_id.index).as_mut().expect(
                 "Tree::get_mut_unsafe: An invalid NodeId made it past id_tree's internal \
@@ -1693,45 +2585,111 @@ impl<T> Tree<T> {
         }
     }
 }
+
+///
+/// A `Tree` as it is deserialized, before it has been checked for consistency.
+///
+#[cfg(feature = "serde_support")]
+#[derive(Deserialize)]
+#[serde(rename = "Tree")]
+struct RawTree<T> {
+    root: Option<NodeId>,
+    nodes: Vec<Option<Node<T>>>,
+    free_ids: Vec<NodeId>,
+}
+
+#[cfg(feature = "serde_support")]
+impl<T> std::convert::TryFrom<RawTree<T>> for Tree<T> {
+    type Error = String;
+
+    fn try_from(raw: RawTree<T>) -> Result<Tree<T>, String> {
+        let tree = Tree {
+            root: raw.root,
+            nodes: raw.nodes,
+            free_ids: raw.free_ids,
+            observers: Observers::default(),
+            self_check: false,
+        };
+
+        tree.validate().map_err(|violations| {
+            let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            format!("invalid Tree: {}", messages.join("; "))
+        })?;
+        Ok(tree)
+    }
+}
+
 impl<T> Default for Tree<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+///
+/// Indexing a `Tree` with a `NodeId` gives the data of that `Node`.
+///
+/// # Panics
+/// Panics if the `NodeId` is no longer valid.  Use `Tree::get_data` to get a `NodeIdError`
+/// instead.
+///
+/// ```
+/// use id_tree::*;
+/// use id_tree::InsertBehavior::*;
+///
+/// let mut tree: Tree<i32> = Tree::new();
+/// let root_id = tree.insert(Node::new(5), AsRoot).unwrap();
+///
+/// tree[&root_id] += 1;
+/// assert_eq!(tree[&root_id], 6);
+/// ```
+///
+impl<T> Index<&NodeId> for Tree<T> {
+    type Output = T;
+
+    fn index(&self, node_id: &NodeId) -> &T {
+        match self.get_data(node_id) {
+            Ok(data) => data,
+            Err(error) => panic!("Tree::index: {:?} is not in the Tree: {}", node_id, error),
+        }
+    }
+}
+
+impl<T> IndexMut<&NodeId> for Tree<T> {
+    fn index_mut(&mut self, node_id: &NodeId) -> &mut T {
+        match self.get_data_mut(node_id) {
+            Ok(data) => data,
+            Err(error) => panic!(
+                "Tree::index_mut: {:?} is not in the Tree: {}",
+                node_id, error
+            ),
+        }
+    }
+}
+
 impl<T> PartialEq for Tree<T>
 where
     T: PartialEq,
 {
     fn eq(&self, other: &Tree<T>) -> bool {
-        if self.nodes.iter().filter(|x| x.is_some()).count()
-            != other.nodes.iter().filter(|x| x.is_some()).count()
-        {
-            return false;
-        }
+        self.structurally_eq(other)
+    }
+}
 
-        for ((i, node1), (j, node2)) in self
-            .nodes
-            .iter()
-            .enumerate()
-            .filter_map(|(i, x)| (*x).as_ref().map(|x| (i, x)))
-            .zip(
-                other
-                    .nodes
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, x)| (*x).as_ref().map(|x| (i, x))),
-            )
-        {
-            let parent1_node = node1.parent.as_ref().and_then(|x| self.get(x).ok());
-            let parent2_node = node2.parent.as_ref().and_then(|x| other.get(x).ok());
+impl<T> Eq for Tree<T> where T: Eq {}
 
-            if i != j || node1 != node2 || parent1_node != parent2_node {
-                return false;
+impl<T> Hash for Tree<T>
+where
+    T: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hash the same things `structurally_eq` compares: the data and child count of every Node
+        // reachable from the root, in pre-order.
+        if let Some(root_id) = self.root_node_id() {
+            for node in self.traverse_pre_order(root_id).unwrap() {
+                node.data().hash(state);
+                node.children().len().hash(state);
             }
         }
-
-        true
     }
 }
 
@@ -1771,51 +2729,7 @@ impl<T: std::fmt::Debug> Tree<T> {
     /// assert_eq!(&s, "");
     /// ```
     pub fn write_formatted<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        if let Some(node_id) = self.root_node_id() {
-            let childn = 0;
-            let level = 0;
-            let last = vec![];
-            let mut stack = vec![(node_id, childn, level, last)];
-            while let Some((node_id, childn, level, last)) = stack.pop() {
-                debug_assert_eq!(
-                    last.len(),
-                    level,
-                    "each previous level should indicate whether it has reached the last node"
-                );
-                let node = self
-                    .get(node_id)
-                    .expect("getting node of existing node ref id");
-                if childn == 0 {
-                    for i in 1..level {
-                        if last[i - 1] {
-                            write!(w, "    ")?;
-                        } else {
-                            write!(w, "│   ")?;
-                        }
-                    }
-                    if level > 0 {
-                        if last[level - 1] {
-                            write!(w, "└── ")?;
-                        } else {
-                            write!(w, "├── ")?;
-                        }
-                    }
-                    writeln!(w, "{:?}", node.data())?;
-                }
-                let mut children = node.children().iter().skip(childn);
-                if let Some(child_id) = children.next() {
-                    let mut next_last = last.clone();
-                    if children.next().is_some() {
-                        stack.push((node_id, childn + 1, level, last));
-                        next_last.push(false);
-                    } else {
-                        next_last.push(true);
-                    }
-                    stack.push((child_id, 0, level + 1, next_last));
-                }
-            }
-        }
-        Ok(())
+        write!(w, "{}", TreeFormatter::debug(self))
     }
 }
 
@@ -1889,6 +2803,7 @@ mod tree_builder_tests {
 
 #[cfg(test)]
 mod tree_tests {
+    use super::super::GetManyMutError;
     use super::super::Node;
     use super::super::NodeId;
     use super::Tree;
@@ -2890,6 +3805,7 @@ mod tree_tests {
     #[test]
     fn test_partial_eq() {
         use InsertBehavior::*;
+        use RemoveBehavior::*;
 
         let mut tree = Tree::new();
         let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
@@ -2934,7 +3850,7 @@ mod tree_tests {
             other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
             other.insert(Node::new(3), UnderNode(&id)).unwrap();
             let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
-            other.take_node(to_delete);
+            other.remove_node(to_delete, DropChildren).unwrap();
             assert_ne!(
                 tree.nodes.iter().filter(|x| x.is_none()).count(),
                 other.nodes.iter().filter(|x| x.is_none()).count()
@@ -2942,7 +3858,7 @@ mod tree_tests {
             assert_eq!(tree, other);
         }
 
-        // ensure PartialEq doesn't work when the Node's index are different
+        // ensure PartialEq works even if the Node's index are different
         {
             let mut other = Tree::new();
             let root_id = other.insert(Node::new(0), AsRoot).unwrap();
@@ -2950,14 +3866,100 @@ mod tree_tests {
             let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
             other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
             other.insert(Node::new(3), UnderNode(&id)).unwrap();
-            other.take_node(to_delete);
+            other.remove_node(to_delete, DropChildren).unwrap();
+            assert_eq!(tree, other);
+        }
+
+        // ensure PartialEq works when the Nodes were inserted in a different order
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(3), UnderNode(&id)).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            assert_eq!(tree, other);
+        }
+
+        // ensure PartialEq doesn't work when the children are in a different order
+        {
+            let mut other = Tree::new();
+            let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+            other.insert(Node::new(2), UnderNode(&root_id)).unwrap();
+            let id = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+            other.insert(Node::new(3), UnderNode(&id)).unwrap();
             assert_ne!(tree, other);
+            assert!(tree.structurally_eq_unordered(&other));
+        }
+
+        // ensure empty Trees are only equal to each other
+        {
+            let empty: Tree<i32> = Tree::new();
+            assert_eq!(empty, Tree::new());
+            assert_ne!(tree, empty);
         }
     }
 
+    #[test]
+    fn test_structurally_eq_unordered() {
+        use InsertBehavior::*;
+
+        //    0         0
+        //   / \       / \
+        //  1   1     1   1
+        //  |   |     |   |
+        //  2   3     3   2
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let a = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let b = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&a)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&b)).unwrap();
+
+        let mut other = Tree::new();
+        let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+        let a = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let b = other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        other.insert(Node::new(3), UnderNode(&a)).unwrap();
+        let id = other.insert(Node::new(2), UnderNode(&b)).unwrap();
+
+        assert!(!tree.structurally_eq(&other));
+        assert!(tree.structurally_eq_unordered(&other));
+
+        other.insert(Node::new(4), UnderNode(&id)).unwrap();
+        assert!(!tree.structurally_eq_unordered(&other));
+    }
+
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        fn hash_of(tree: &Tree<i32>) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            tree.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        let mut other = Tree::new();
+        let root_id = other.insert(Node::new(0), AsRoot).unwrap();
+        let to_delete = other.insert(Node::new(42), UnderNode(&root_id)).unwrap();
+        other.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        other.remove_node(to_delete, DropChildren).unwrap();
+
+        assert_eq!(tree, other);
+        assert_eq!(hash_of(&tree), hash_of(&other));
+    }
+
     #[test]
     fn test_clone() {
         use InsertBehavior::*;
+        use RemoveBehavior::*;
 
         let mut tree = Tree::new();
         let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
@@ -2965,7 +3967,7 @@ mod tree_tests {
         let node_2_id = tree.insert(Node::new(2), UnderNode(&root_id)).unwrap();
         let _node_3_id = tree.insert(Node::new(3), UnderNode(&node_1_id)).unwrap();
         let node_4_id = tree.insert(Node::new(4), UnderNode(&node_2_id)).unwrap();
-        tree.take_node(node_4_id);
+        tree.remove_node(node_4_id, DropChildren).unwrap();
 
         let cloned = tree.clone();
         assert!(cloned.root.is_some());
@@ -2973,4 +3975,410 @@ mod tree_tests {
         // ensure the tree and the cloned tree are equal
         assert_eq!(tree, cloned);
     }
+
+    #[test]
+    fn test_index() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+
+        tree[&child_id] = 10;
+        *tree.get_data_mut(&root_id).unwrap() += 5;
+        assert_eq!(tree[&root_id], 5);
+        assert_eq!(tree.get_data(&child_id), Ok(&10));
+
+        tree.remove_node(child_id.clone(), DropChildren).unwrap();
+        assert!(tree.get_data(&child_id).is_err());
+        assert!(tree.get_data_mut(&child_id).is_err());
+    }
+
+    #[test]
+    fn test_get_many_mut() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(Node::new(2), UnderNode(&node_1)).unwrap();
+
+        let [two, zero] = tree.get_many_mut([&node_2, &root_id]).unwrap();
+        std::mem::swap(two.data_mut(), zero.data_mut());
+        assert_eq!(tree[&root_id], 2);
+        assert_eq!(tree[&node_2], 0);
+
+        let (one, two) = tree.get_pair_mut(&node_1, &node_2).unwrap();
+        *one.data_mut() += *two.data();
+        assert_eq!(tree[&node_1], 1);
+
+        // every reference is to the right Node, whatever order the NodeIds come in
+        let [a, b, c] = tree.get_many_mut([&node_1, &node_2, &root_id]).unwrap();
+        assert_eq!((*a.data(), *b.data(), *c.data()), (1, 0, 2));
+        let [a, b, c] = tree.get_many_mut([&root_id, &node_2, &node_1]).unwrap();
+        assert_eq!((*a.data(), *b.data(), *c.data()), (2, 0, 1));
+
+        assert!(tree.get_many_mut::<0>([]).is_ok());
+        assert_eq!(
+            tree.get_pair_mut(&node_1, &node_1).err(),
+            Some(GetManyMutError::Duplicate {
+                first: 0,
+                second: 1,
+            })
+        );
+
+        tree.remove_node(node_2.clone(), DropChildren).unwrap();
+        assert_eq!(
+            tree.get_many_mut([&root_id, &node_1, &node_2]).err(),
+            Some(GetManyMutError::NodeIdNoLongerValid { position: 2 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "is not in the Tree")]
+    fn test_index_stale_id() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        tree.remove_node(root_id.clone(), DropChildren).unwrap();
+        let _ = tree[&root_id];
+    }
+
+    #[test]
+    fn test_len_and_sizes() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        assert!(tree.is_empty());
+
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let node_1 = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        let node_2 = tree.insert(Node::new(2), UnderNode(&node_1)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&node_2)).unwrap();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.subtree_size(&node_1), Ok(3));
+
+        tree.remove_node(node_2.clone(), OrphanChildren).unwrap();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.free_slot_count(), 1);
+        assert_eq!(tree.subtree_size(&root_id), Ok(2));
+        assert!(tree.subtree_size(&node_2).is_err());
+
+        // rolled back changes don't throw the count off
+        let result: Result<(), ()> = tree.transaction(|tx| {
+            tx.insert(Node::new(4), UnderNode(&root_id)).unwrap();
+            tx.remove_node(node_1.clone(), DropChildren).unwrap();
+            Err(())
+        });
+        assert!(result.is_err());
+        assert_eq!(tree.len(), 3);
+
+        tree.remove_node(root_id, DropChildren).unwrap();
+        assert_eq!(tree.len(), 1);
+        assert!(!tree.is_empty());
+    }
+
+    #[test]
+    fn test_memory_stats() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree: Tree<u8> = TreeBuilder::new()
+            .with_node_capacity(3)
+            .with_swap_capacity(2)
+            .build();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.remove_node(child_id, DropChildren).unwrap();
+
+        let id_size = std::mem::size_of::<NodeId>();
+        let stats = tree.memory_stats();
+        assert_eq!(stats.nodes, 3 * std::mem::size_of::<Option<Node<u8>>>());
+        assert_eq!(stats.free_ids, 2 * id_size);
+        assert_eq!(
+            stats.children,
+            tree.get(&root_id).unwrap().children().capacity() * id_size
+        );
+        assert_eq!(stats.total(), stats.nodes + stats.children + stats.free_ids);
+    }
+}
+
+#[cfg(all(test, feature = "serde_support"))]
+mod tree_deserialize_tests {
+    use serde_json;
+
+    use super::super::*;
+
+    fn node(data: i32, parent: Option<usize>, children: &[usize]) -> String {
+        let parent = match parent {
+            Some(index) => format!("{{\"index\":{}}}", index),
+            None => "null".to_string(),
+        };
+        let children: Vec<String> = children
+            .iter()
+            .map(|index| format!("{{\"index\":{}}}", index))
+            .collect();
+        format!(
+            "{{\"data\":{},\"parent\":{},\"children\":[{}]}}",
+            data,
+            parent,
+            children.join(",")
+        )
+    }
+
+    fn raw_tree(root: Option<usize>, nodes: &[Option<String>], free_ids: &[usize]) -> String {
+        let root = match root {
+            Some(index) => format!("{{\"index\":{}}}", index),
+            None => "null".to_string(),
+        };
+        let nodes: Vec<String> = nodes
+            .iter()
+            .map(|node| node.clone().unwrap_or_else(|| "null".to_string()))
+            .collect();
+        let free_ids: Vec<String> = free_ids
+            .iter()
+            .map(|index| format!("{{\"index\":{}}}", index))
+            .collect();
+        format!(
+            "{{\"root\":{},\"nodes\":[{}],\"free_ids\":[{}]}}",
+            root,
+            nodes.join(","),
+            free_ids.join(",")
+        )
+    }
+
+    fn error(json: &str) -> String {
+        serde_json::from_str::<Tree<i32>>(json)
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use InsertBehavior::*;
+        use RemoveBehavior::*;
+
+        let mut tree = Tree::new();
+        let root_id = tree.insert(Node::new(0), AsRoot).unwrap();
+        let child_id = tree.insert(Node::new(1), UnderNode(&root_id)).unwrap();
+        tree.insert(Node::new(2), UnderNode(&child_id)).unwrap();
+        tree.insert(Node::new(3), UnderNode(&root_id)).unwrap();
+        tree.remove_node(child_id, DropChildren).unwrap();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let deserialized: Tree<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, tree);
+        assert_eq!(deserialized.free_ids, tree.free_ids);
+
+        let valid = raw_tree(
+            Some(0),
+            &[Some(node(0, None, &[1])), Some(node(1, Some(0), &[])), None],
+            &[2],
+        );
+        assert!(serde_json::from_str::<Tree<i32>>(&valid).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_links() {
+        let out_of_bounds = raw_tree(Some(0), &[Some(node(0, None, &[5]))], &[]);
+        assert!(error(&out_of_bounds).contains("not in the Tree"));
+
+        let asymmetric = raw_tree(
+            Some(0),
+            &[Some(node(0, None, &[])), Some(node(1, Some(0), &[]))],
+            &[],
+        );
+        assert!(error(&asymmetric).contains("is not a child of its parent"));
+
+        let wrong_parent = raw_tree(
+            Some(0),
+            &[
+                Some(node(0, None, &[1, 2])),
+                Some(node(1, Some(0), &[])),
+                Some(node(2, Some(1), &[])),
+            ],
+            &[],
+        );
+        assert!(error(&wrong_parent).contains("whose parent is something else"));
+
+        let duplicate = raw_tree(
+            Some(0),
+            &[Some(node(0, None, &[1, 1])), Some(node(1, Some(0), &[]))],
+            &[],
+        );
+        assert!(error(&duplicate).contains("more than once"));
+    }
+
+    #[test]
+    fn test_invalid_shape() {
+        let cycle = raw_tree(
+            Some(0),
+            &[
+                Some(node(0, None, &[])),
+                Some(node(1, Some(2), &[2])),
+                Some(node(2, Some(1), &[1])),
+            ],
+            &[],
+        );
+        assert!(error(&cycle).contains("its own ancestor"));
+
+        let root_with_parent = raw_tree(
+            Some(1),
+            &[Some(node(0, None, &[1])), Some(node(1, Some(0), &[]))],
+            &[],
+        );
+        assert!(error(&root_with_parent).contains("the root"));
+
+        let missing_root = raw_tree(Some(1), &[Some(node(0, None, &[])), None], &[1]);
+        assert!(error(&missing_root).contains("the root"));
+    }
+
+    #[test]
+    fn test_invalid_free_ids() {
+        let unlisted = raw_tree(Some(0), &[Some(node(0, None, &[])), None], &[]);
+        assert!(error(&unlisted).contains("neither in use nor free"));
+
+        let in_use = raw_tree(Some(0), &[Some(node(0, None, &[])), None], &[0, 1]);
+        assert!(error(&in_use).contains("free but in use"));
+
+        let out_of_bounds = raw_tree(Some(0), &[Some(node(0, None, &[]))], &[1]);
+        assert!(error(&out_of_bounds).contains("out of bounds"));
+
+        let twice = raw_tree(Some(0), &[Some(node(0, None, &[])), None], &[1, 1]);
+        assert!(error(&twice).contains("free more than once"));
+    }
+}
+
+#[cfg(test)]
+mod tree_validate_tests {
+    use super::super::*;
+
+    #[test]
+    fn test_valid_after_changes() {
+        use InsertBehavior::*;
+        use MoveBehavior::*;
+        use RemoveBehavior::*;
+        use SwapBehavior::*;
+
+        tree!(let mut tree = 0 => [node_1 @ 1 => [node_3 @ 3], node_2 @ 2]);
+        tree.set_self_check(true);
+        assert_eq!(tree.validate(), Ok(()));
+
+        tree.swap_nodes(&node_1, &node_2, LeaveChildren).unwrap();
+        tree.swap_nodes(&node_1, &node_2, ChildrenOnly).unwrap();
+        tree.move_node(&node_2, ToRoot).unwrap();
+        tree.make_nth_sibling(&node_1, 0).unwrap();
+        tree.remove_node(node_3.clone(), OrphanChildren).unwrap();
+        tree.insert(Node::new(4), UnderNode(&node_1)).unwrap();
+        tree.remove_node(node_1.clone(), LiftChildren).unwrap();
+
+        assert_eq!(tree.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_broken_links() {
+        tree!(let mut tree = root_id @ 0 => [node_1 @ 1 => [node_3 @ 3], node_2 @ 2]);
+
+        tree.get_mut_unsafe(&root_id).children.push(node_3.clone());
+        tree.get_mut_unsafe(&node_2).parent = Some(node_3.clone());
+        tree.get_mut_unsafe(&node_1)
+            .children
+            .push(NodeId { index: 7 });
+
+        let violations = tree.validate().unwrap_err();
+        assert!(
+            violations.contains(&InvariantViolation::ChildHasOtherParent {
+                node: root_id.clone(),
+                child: node_3.clone(),
+            })
+        );
+        assert!(violations.contains(&InvariantViolation::NotChildOfParent {
+            node: node_2.clone(),
+            parent: node_3.clone(),
+        }));
+        assert!(violations.contains(&InvariantViolation::ChildNotInTree {
+            node: node_1.clone(),
+            child: NodeId { index: 7 },
+        }));
+    }
+
+    #[test]
+    fn test_cycle() {
+        use InsertBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [node_1 @ 1 => [node_3 @ 3], 2]);
+        let node_4 = tree.insert(Node::new(4), UnderNode(&node_3)).unwrap();
+
+        // detach 1 from the root and hang it below 3, its own child
+        tree.get_mut_unsafe(&root_id)
+            .children
+            .retain(|id| id != &node_1);
+        tree.get_mut_unsafe(&node_1).parent = Some(node_3.clone());
+        tree.get_mut_unsafe(&node_3).children.push(node_1.clone());
+
+        let violations = tree.validate().unwrap_err();
+        assert_eq!(
+            violations,
+            vec![
+                InvariantViolation::Cycle(node_1.clone()),
+                InvariantViolation::Unreachable(node_4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_children() {
+        use InsertBehavior::*;
+
+        tree!(let mut tree = 0 => [node_1 @ 1 => [3], node_2 @ 2]);
+        let wide: Vec<NodeId> = (0..100_000)
+            .map(|data| tree.insert(Node::new(data), UnderNode(&node_2)).unwrap())
+            .collect();
+        assert_eq!(tree.validate(), Ok(()));
+
+        tree.get_mut_unsafe(&node_2).children.push(wide[5].clone());
+        tree.get_mut_unsafe(&node_1)
+            .children
+            .extend(vec![NodeId { index: 200_000 }, NodeId { index: 200_000 }]);
+
+        let violations = tree.validate().unwrap_err();
+        assert!(violations.contains(&InvariantViolation::DuplicateChild {
+            node: node_2.clone(),
+            child: wide[5].clone(),
+        }));
+        assert!(violations.contains(&InvariantViolation::DuplicateChild {
+            node: node_1.clone(),
+            child: NodeId { index: 200_000 },
+        }));
+    }
+
+    #[test]
+    fn test_free_ids() {
+        use RemoveBehavior::*;
+
+        tree!(let mut tree = root_id @ 0 => [1 => [3], node_2 @ 2]);
+        tree.remove_node(node_2.clone(), DropChildren).unwrap();
+        assert_eq!(tree.validate(), Ok(()));
+
+        tree.free_ids.push(node_2.clone());
+        tree.free_ids.push(root_id.clone());
+        tree.free_ids.push(NodeId { index: 9 });
+        tree.nodes.push(None);
+
+        assert_eq!(
+            tree.validate(),
+            Err(vec![
+                InvariantViolation::DuplicateFreeId(node_2.clone()),
+                InvariantViolation::FreeIdInUse(root_id.clone()),
+                InvariantViolation::FreeIdOutOfBounds(NodeId { index: 9 }),
+                InvariantViolation::SlotNotFree(NodeId { index: 4 }),
+            ])
+        );
+    }
 }